@@ -0,0 +1,509 @@
+use std::cmp::Ordering;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::TimeDelta;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use mavlink::MavHeader;
+use mavlink::common::MavMessage;
+
+use crate::dialect::DialectMessage;
+use crate::dialect::to_common;
+use crate::message_fields::FieldValue;
+use crate::status_text::SEVERITIES;
+use crate::status_text::severity_name;
+use crate::utils::mavlink::parse_status_text;
+use crate::watch::format_value;
+
+/// Messages reporting events rather than a state, every match of their field rules alerts
+const EVENT_MESSAGES: [&str; 4] = ["STATUSTEXT", "COMMAND_ACK", "MISSION_ACK", "EVENT"];
+/// Shortest time between two alerts of a rule on event messages
+const EVENT_ALERT_INTERVAL: TimeDelta = TimeDelta::seconds(1);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn parse(text: &str) -> Option<Self> {
+        Some(match text {
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            "==" | "=" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            _ => return None,
+        })
+    }
+
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Less => ordering.is_lt(),
+            Comparison::LessOrEqual => ordering.is_le(),
+            Comparison::Greater => ordering.is_gt(),
+            Comparison::GreaterOrEqual => ordering.is_ge(),
+            Comparison::Equal => ordering.is_eq(),
+            Comparison::NotEqual => ordering.is_ne(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Threshold {
+    Number(f64),
+    /// Enum entry or text, compared for equality only
+    Name(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Condition {
+    /// No matching message for longer than this
+    Missing(TimeDelta),
+    Compare {
+        field_name: String,
+        comparison: Comparison,
+        threshold: Threshold,
+    },
+}
+
+/// Condition on the messages of one type, optionally from one sender
+pub struct AlertRule {
+    /// Rule as written by the user
+    pub text: String,
+    message_name: String,
+    source: Option<(u8, u8)>,
+    condition: Condition,
+    last_seen: Option<DateTime<Local>>,
+    /// Time the condition started to hold, none while it does not
+    pub active_since: Option<DateTime<Local>>,
+    /// What made the rule trigger last
+    pub detail: Option<String>,
+    last_triggered: Option<DateTime<Local>>,
+}
+
+impl AlertRule {
+    /// Parses rules like `HEARTBEAT from 1:1 missing > 3 s`, `SYS_STATUS.battery_remaining < 20`
+    /// or `STATUSTEXT severity <= ERROR`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("Invalid alert rule \"{}\": {}", text, reason);
+        let mut tokens = text.split_whitespace().peekable();
+        let subject = tokens.next().ok_or_else(|| invalid("empty rule"))?;
+        let (message_name, mut field_name) = match subject.split_once('.') {
+            Some((message_name, field_name)) => (message_name, Some(field_name.to_string())),
+            None => (subject, None),
+        };
+        if field_name.is_none()
+            && let Some(token) = tokens
+                .next_if(|t| !matches!(*t, "from" | "missing") && Comparison::parse(t).is_none())
+        {
+            field_name = Some(token.to_string());
+        }
+        let mut source = None;
+        if tokens.next_if_eq(&"from").is_some() {
+            let (system_id, component_id) = tokens
+                .next()
+                .and_then(|t| t.split_once(':'))
+                .and_then(|(s, c)| Some((s.parse().ok()?, c.parse().ok()?)))
+                .ok_or_else(|| invalid("expected system:component after from"))?;
+            source = Some((system_id, component_id));
+        }
+        let condition = if tokens.next_if_eq(&"missing").is_some() {
+            if field_name.is_some() {
+                return Err(invalid("missing applies to messages, not fields"));
+            }
+            if tokens.next() != Some(">") {
+                return Err(invalid("expected missing > seconds"));
+            }
+            let seconds = tokens
+                .next()
+                .map(|t| t.trim_end_matches('s'))
+                .and_then(|t| t.parse::<f64>().ok())
+                .filter(|s| *s > 0.0)
+                .ok_or_else(|| invalid("expected missing > seconds"))?;
+            tokens.next_if_eq(&"s");
+            Condition::Missing(TimeDelta::milliseconds((seconds * 1000.0) as i64))
+        } else {
+            let field_name = field_name.ok_or_else(|| invalid("expected a field or missing"))?;
+            let comparison = tokens
+                .next()
+                .and_then(Comparison::parse)
+                .ok_or_else(|| invalid("expected one of < <= > >= == !="))?;
+            let value = tokens.next().ok_or_else(|| invalid("expected a value"))?;
+            let threshold = match value.parse::<f64>() {
+                Ok(number) => Threshold::Number(number),
+                // severities are ordered, lower is more severe
+                Err(_) if message_name == "STATUSTEXT" && field_name == "severity" => {
+                    let level = SEVERITIES
+                        .iter()
+                        .position(|s| severity_name(*s).eq_ignore_ascii_case(value))
+                        .ok_or_else(|| invalid("unknown severity"))?;
+                    Threshold::Number(level as f64)
+                }
+                Err(_) if matches!(comparison, Comparison::Equal | Comparison::NotEqual) => {
+                    Threshold::Name(value.to_string())
+                }
+                Err(_) => return Err(invalid("names can only be compared with == or !=")),
+            };
+            Condition::Compare {
+                field_name,
+                comparison,
+                threshold,
+            }
+        };
+        if let Some(token) = tokens.next() {
+            return Err(invalid(&format!("unexpected \"{}\"", token)));
+        }
+        Ok(AlertRule {
+            text: text.trim().to_string(),
+            message_name: message_name.to_string(),
+            source,
+            condition,
+            last_seen: None,
+            active_since: None,
+            detail: None,
+            last_triggered: None,
+        })
+    }
+
+    fn matches(&self, header: &MavHeader, message_name: &str) -> bool {
+        self.message_name == message_name
+            && self
+                .source
+                .is_none_or(|s| s == (header.system_id, header.component_id))
+    }
+
+    fn is_on_events(&self) -> bool {
+        matches!(self.condition, Condition::Compare { .. })
+            && EVENT_MESSAGES.contains(&self.message_name.as_str())
+    }
+
+    /// Marks the rule active or not, true when it has just become active
+    /// or, for event messages, on every match at most once per EVENT_ALERT_INTERVAL
+    fn update(&mut self, is_holding: bool, detail: String, now: DateTime<Local>) -> bool {
+        if !is_holding {
+            self.active_since = None;
+            return false;
+        }
+        let is_repeated = if self.is_on_events() {
+            self.last_triggered
+                .is_some_and(|t| now - t < EVENT_ALERT_INTERVAL)
+        } else {
+            self.active_since.is_some()
+        };
+        if is_repeated {
+            return false;
+        }
+        self.active_since = Some(now);
+        self.detail = Some(detail);
+        self.last_triggered = Some(now);
+        true
+    }
+}
+
+/// Value of the compared field and whether the comparison holds
+fn compare<M: DialectMessage>(
+    message: &M,
+    field_name: &str,
+    comparison: Comparison,
+    threshold: &Threshold,
+) -> Option<(bool, String)> {
+    // severity entries have no order by name, compare their levels
+    if let (Some(MavMessage::STATUSTEXT(data)), "severity", Threshold::Number(level)) =
+        (to_common(message), field_name, threshold)
+    {
+        let value = data.severity as u8 as f64;
+        let shown = format!(
+            "{}: {}",
+            severity_name(data.severity),
+            parse_status_text(&data.text)
+        );
+        return Some((comparison.holds(value.partial_cmp(level)?), shown));
+    }
+    let field = message
+        .fields()
+        .into_iter()
+        .find(|f| f.name == field_name)?;
+    match (threshold, &field.value) {
+        (Threshold::Number(number), _) => {
+            let value = field.numeric_value()?;
+            let shown = format!("{} = {}", field_name, format_value(value));
+            Some((comparison.holds(value.partial_cmp(number)?), shown))
+        }
+        (Threshold::Name(name), FieldValue::Enum(value) | FieldValue::Text(value)) => {
+            // enum entries may be given without the enum prefix, ex. CRITICAL for MAV_STATE_CRITICAL
+            let is_equal = value == name || value.ends_with(&format!("_{}", name));
+            let ordering = if is_equal {
+                Ordering::Equal
+            } else {
+                Ordering::Less
+            };
+            Some((
+                comparison.holds(ordering),
+                format!("{} = {}", field_name, value),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Triggered alert kept in the history
+pub struct Alert {
+    pub time: DateTime<Local>,
+    pub rule: String,
+    pub detail: String,
+}
+
+/// Rules from --alert and --alerts-file with the alerts they triggered
+#[derive(Default)]
+pub struct Alerts {
+    pub rules: Vec<AlertRule>,
+    pub history: Vec<Alert>,
+}
+
+impl Alerts {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Alerts {
+            rules,
+            history: Vec::new(),
+        }
+    }
+
+    /// Rules given on the command line and in a file with a rule per line and # comments
+    pub fn load(rules: &[String], file: Option<&Path>) -> Result<Self> {
+        let mut texts = rules.to_vec();
+        if let Some(path) = file {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| eyre!("Could not read {}: {}", path.display(), e))?;
+            texts.extend(
+                text.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string),
+            );
+        }
+        let rules = texts
+            .iter()
+            .map(|text| AlertRule::parse(text).map_err(|e| eyre!(e)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Alerts::new(rules))
+    }
+
+    /// Evaluates the rules of the message type, returns the number of new alerts
+    pub fn check_message<M: DialectMessage>(
+        &mut self,
+        header: &MavHeader,
+        message: &M,
+        now: DateTime<Local>,
+    ) -> usize {
+        let message_name = message.message_name();
+        let mut triggered = 0;
+        for rule in self
+            .rules
+            .iter_mut()
+            .filter(|r| r.matches(header, message_name))
+        {
+            let source = format!("from {}:{}", header.system_id, header.component_id);
+            let is_new = match &rule.condition {
+                Condition::Missing(_) => {
+                    rule.last_seen = Some(now);
+                    rule.active_since = None;
+                    false
+                }
+                Condition::Compare {
+                    field_name,
+                    comparison,
+                    threshold,
+                } => match compare(message, field_name, *comparison, threshold) {
+                    Some((is_holding, shown)) => {
+                        rule.update(is_holding, format!("{} {}", shown, source), now)
+                    }
+                    None => false,
+                },
+            };
+            if is_new {
+                triggered += 1;
+                self.history.push(Alert {
+                    time: now,
+                    rule: rule.text.clone(),
+                    detail: rule.detail.clone().unwrap_or_default(),
+                });
+            }
+        }
+        triggered
+    }
+
+    /// Evaluates the timing rules, returns the number of new alerts
+    pub fn check_time(&mut self, now: DateTime<Local>) -> usize {
+        let mut triggered = 0;
+        for rule in &mut self.rules {
+            let Condition::Missing(limit) = rule.condition else {
+                continue;
+            };
+            // messages missing from the start count from the first check
+            let last_seen = *rule.last_seen.get_or_insert(now);
+            let silence = now - last_seen;
+            let detail = format!(
+                "no {} for {:.1} s",
+                rule.message_name,
+                silence.num_milliseconds() as f64 / 1000.0
+            );
+            if rule.update(silence > limit, detail, now) {
+                triggered += 1;
+                self.history.push(Alert {
+                    time: now,
+                    rule: rule.text.clone(),
+                    detail: rule.detail.clone().unwrap_or_default(),
+                });
+            }
+        }
+        triggered
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &AlertRule> {
+        self.rules.iter().filter(|r| r.active_since.is_some())
+    }
+
+    /// Forgets the state of the rules and the history, when a replay starts over
+    pub fn reset(&mut self) {
+        for rule in &mut self.rules {
+            rule.last_seen = None;
+            rule.active_since = None;
+            rule.detail = None;
+            rule.last_triggered = None;
+        }
+        self.history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use chrono::TimeDelta;
+    use mavlink::MavHeader;
+    use mavlink::common::MavMessage;
+    use mavlink::common::MavSeverity;
+    use mavlink::common::STATUSTEXT_DATA;
+    use mavlink::common::SYS_STATUS_DATA;
+
+    use crate::alerts::AlertRule;
+    use crate::alerts::Alerts;
+    use crate::dialect::ExtendedMessage;
+
+    fn header(component_id: u8) -> MavHeader {
+        MavHeader {
+            system_id: 1,
+            component_id,
+            sequence: 0,
+        }
+    }
+
+    fn rules(texts: &[&str]) -> Alerts {
+        Alerts::new(texts.iter().map(|t| AlertRule::parse(t).unwrap()).collect())
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        for text in [
+            "HEARTBEAT from 1:1 missing > 3 s",
+            "HEARTBEAT missing > 3s",
+            "SYS_STATUS.battery_remaining < 20",
+            "SYS_STATUS battery_remaining from 1:1 < 20",
+            "STATUSTEXT severity <= ERROR",
+            "HEARTBEAT.system_status == CRITICAL",
+        ] {
+            assert!(AlertRule::parse(text).is_ok(), "{}", text);
+        }
+        for text in [
+            "",
+            "HEARTBEAT missing 3",
+            "HEARTBEAT.type missing > 3 s",
+            "SYS_STATUS.battery_remaining < low",
+            "STATUSTEXT severity <= LOUD",
+            "SYS_STATUS.battery_remaining < 20 %",
+            "HEARTBEAT from 1 missing > 3 s",
+        ] {
+            assert!(AlertRule::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_field_rule() {
+        let mut alerts = rules(&["SYS_STATUS.battery_remaining < 20"]);
+        let now = Local::now();
+        let sys_status = |battery_remaining| {
+            ExtendedMessage::Compiled(MavMessage::SYS_STATUS(SYS_STATUS_DATA {
+                battery_remaining,
+                ..Default::default()
+            }))
+        };
+        assert_eq!(alerts.check_message(&header(1), &sys_status(50), now), 0);
+        assert_eq!(alerts.check_message(&header(1), &sys_status(19), now), 1);
+        // stays active without alerting again
+        assert_eq!(alerts.check_message(&header(1), &sys_status(18), now), 0);
+        assert_eq!(alerts.active().count(), 1);
+        assert_eq!(alerts.history[0].detail, "battery_remaining = 19 from 1:1");
+        alerts.check_message(&header(1), &sys_status(80), now);
+        assert_eq!(alerts.active().count(), 0);
+    }
+
+    #[test]
+    fn test_severity_rule() {
+        let mut alerts = rules(&["STATUSTEXT severity <= ERROR"]);
+        let status_text = |severity| {
+            ExtendedMessage::Compiled(MavMessage::STATUSTEXT(STATUSTEXT_DATA {
+                severity,
+                ..Default::default()
+            }))
+        };
+        let now = Local::now();
+        let warning = status_text(MavSeverity::MAV_SEVERITY_WARNING);
+        assert_eq!(alerts.check_message(&header(1), &warning, now), 0);
+        let critical = status_text(MavSeverity::MAV_SEVERITY_CRITICAL);
+        assert_eq!(alerts.check_message(&header(1), &critical, now), 1);
+    }
+
+    #[test]
+    fn test_event_rule() {
+        let mut alerts = rules(&["STATUSTEXT severity <= ERROR"]);
+        let error = ExtendedMessage::Compiled(MavMessage::STATUSTEXT(STATUSTEXT_DATA {
+            severity: MavSeverity::MAV_SEVERITY_ERROR,
+            ..Default::default()
+        }));
+        let start = Local::now();
+        // every error text alerts, not only the first one
+        assert_eq!(alerts.check_message(&header(1), &error, start), 1);
+        let second = start + TimeDelta::seconds(2);
+        assert_eq!(alerts.check_message(&header(1), &error, second), 1);
+        // a burst alerts once
+        let burst = second + TimeDelta::milliseconds(100);
+        assert_eq!(alerts.check_message(&header(1), &error, burst), 0);
+        assert_eq!(alerts.history.len(), 2);
+    }
+
+    #[test]
+    fn test_missing_rule() {
+        let mut alerts = rules(&["SYS_STATUS from 1:1 missing > 3 s"]);
+        let start = Local::now();
+        let sys_status = ExtendedMessage::Compiled(MavMessage::SYS_STATUS(Default::default()));
+        assert_eq!(alerts.check_time(start), 0);
+        alerts.check_message(&header(1), &sys_status, start + TimeDelta::seconds(2));
+        // another component does not keep the rule quiet
+        alerts.check_message(&header(2), &sys_status, start + TimeDelta::seconds(5));
+        assert_eq!(alerts.check_time(start + TimeDelta::seconds(4)), 0);
+        assert_eq!(alerts.check_time(start + TimeDelta::seconds(6)), 1);
+        assert_eq!(alerts.history[0].detail, "no SYS_STATUS for 4.0 s");
+        assert_eq!(alerts.check_time(start + TimeDelta::seconds(7)), 0);
+
+        alerts.check_message(&header(1), &sys_status, start + TimeDelta::seconds(8));
+        assert_eq!(alerts.active().count(), 0);
+    }
+}
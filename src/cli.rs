@@ -1,4 +1,14 @@
+use std::path::PathBuf;
+
 use clap::Parser;
+use clap::Subcommand;
+use clap::ValueEnum;
+
+use crate::SourceDetails;
+use crate::export::MessageFilter;
+
+/// System id of the debugger when it forwards between addresses, next to the 255 of ground stations
+const FORWARDING_SOURCE_SYSTEM: u8 = 254;
 
 /**
 Simple program to debug Mavlink messages
@@ -7,7 +17,238 @@ Simple program to debug Mavlink messages
 #[command(about)]
 pub struct Args {
     /// (tcpout|tcpin|udpout|udpin|udpbcast|serial|file):(ip|dev|path):(port|baud)
-    /// ex. `tcpout:127.0.0.1:5760`
-    #[arg(required = true)]
-    pub address: String,
+    /// or (tlog|bin|ulg):path to replay a recording or an onboard log, ex. `tcpout:127.0.0.1:5760`.
+    /// Frames received on one of several addresses are forwarded to the others,
+    /// the debugger talks to the vehicle on the first one
+    #[arg(required = true, value_name = "ADDRESS")]
+    pub addresses: Vec<String>,
+
+    /// System id of the vehicle to talk to, by default the first autopilot seen
+    #[arg(long)]
+    pub target_system: Option<u8>,
+
+    /// Component id of the vehicle to talk to, by default the first autopilot seen
+    #[arg(long)]
+    pub target_component: Option<u8>,
+
+    /// System id used in the heartbeat and every message sent by the debugger,
+    /// 255 or, when forwarding between addresses, 254
+    #[arg(long)]
+    pub source_system: Option<u8>,
+
+    /// Component id used in the heartbeat and every message sent by the debugger [default: 190]
+    #[arg(long)]
+    pub source_component: Option<u8>,
+
+    /// Seconds without a heartbeat after which a component is shown as lost
+    #[arg(long, default_value_t = 5)]
+    pub heartbeat_timeout: u64,
+
+    /// Request all legacy data streams at this rate (Hz) as soon as the target is found
+    #[arg(long)]
+    pub stream_rate: Option<u16>,
+
+    /// MAVLink message set used to decode and send messages
+    #[arg(long, value_enum, default_value_t = Dialect::Ardupilotmega)]
+    pub dialect: Dialect,
+
+    /// XML definitions (with includes) of messages the dialect does not know
+    #[arg(long)]
+    pub dialect_xml: Option<PathBuf>,
+
+    /// Only forward messages with these names between addresses, ex. `--forward-message HEARTBEAT,COMMAND_LONG`
+    #[arg(long, value_delimiter = ',')]
+    pub forward_message: Vec<String>,
+
+    /// Never forward messages with these names between addresses, ex. `--drop-message PARAM_VALUE`
+    #[arg(long, value_delimiter = ',')]
+    pub drop_message: Vec<String>,
+
+    /// Only forward messages sent by this system id between addresses
+    #[arg(long)]
+    pub forward_system: Option<u8>,
+
+    /// Listen on every address without forwarding frames between them
+    #[arg(long, conflicts_with_all = ["forward_message", "drop_message", "forward_system"])]
+    pub no_forward: bool,
+
+    /// File with the secret key of MAVLink 2 message signing, 64 hex digits or 32 bytes.
+    /// Received signatures are checked and every sent message is signed
+    #[arg(long, conflicts_with = "signing_passphrase")]
+    pub signing_key: Option<PathBuf>,
+
+    /// Passphrase the signing key is derived from with SHA-256, as QGroundControl does
+    #[arg(long)]
+    pub signing_passphrase: Option<String>,
+
+    /// Link id put into the signature of sent messages
+    #[arg(long, default_value_t = 0)]
+    pub signing_link_id: u8,
+
+    /// Record every received frame to this .tlog file, (Ctrl+r) pauses and resumes
+    #[arg(long)]
+    pub record: Option<PathBuf>,
+
+    /// Alert when a rule holds, ex. `--alert "SYS_STATUS.battery_remaining < 20"`,
+    /// `--alert "HEARTBEAT from 1:1 missing > 3 s"` or `--alert "STATUSTEXT severity <= ERROR"`
+    #[arg(long)]
+    pub alert: Vec<String>,
+
+    /// File with an alert rule per line, lines starting with # are skipped
+    #[arg(long)]
+    pub alerts_file: Option<PathBuf>,
+
+    /// Ring the terminal bell when an alert triggers
+    #[arg(long)]
+    pub alert_bell: bool,
+
+    /// Seconds a command run without the interface waits for the vehicle
+    #[arg(long, default_value_t = 10)]
+    pub timeout: u64,
+
+    /// Run without the interface
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Args {
+    /// Messages forwarded between the addresses, none with `--no-forward`
+    pub fn forward_filter(&self) -> Option<MessageFilter> {
+        (!self.no_forward).then(|| MessageFilter {
+            message_names: self.forward_message.clone(),
+            excluded_message_names: self.drop_message.clone(),
+            system_id: self.forward_system,
+        })
+    }
+
+    /// Ids of the debugger; when it forwards for a ground station it does not take the 255 of
+    /// the ground station by default, or the vehicle would see that one alive through its heartbeats
+    pub fn source_details(&self) -> SourceDetails {
+        let default = SourceDetails::default();
+        let is_forwarding = self.addresses.len() > 1 && !self.no_forward;
+        let default_system_id = if is_forwarding {
+            FORWARDING_SOURCE_SYSTEM
+        } else {
+            default.source_system_id
+        };
+        SourceDetails {
+            source_system_id: self.source_system.unwrap_or(default_system_id),
+            source_component_id: self.source_component.unwrap_or(default.source_component_id),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Write the messages of a recording or an onboard log for other tools, ex.
+    /// `tlog:flight.tlog export --format csv --output flight`
+    Export(ExportArgs),
+    /// Print received messages as JSON Lines
+    Dump(DumpArgs),
+    /// Read and write parameters of the target
+    #[command(subcommand)]
+    Params(ParamsCommand),
+    /// Download and upload the mission of the target as a QGC WPL 110 file
+    #[command(subcommand)]
+    Mission(MissionCommand),
+    /// Send a command to the target and wait for its acknowledgement, ex. `cmd COMPONENT_ARM_DISARM 1`
+    Cmd(CmdArgs),
+}
+
+#[derive(clap::Args)]
+pub struct ExportArgs {
+    #[arg(long, value_enum, default_value_t = ExportFormat::Jsonl)]
+    pub format: ExportFormat,
+
+    /// File for jsonl, directory for csv
+    #[arg(long, short)]
+    pub output: PathBuf,
+
+    /// Only messages with these names, ex. `--message ATTITUDE,GPS_RAW_INT`
+    #[arg(long, value_delimiter = ',')]
+    pub message: Vec<String>,
+
+    /// Only messages sent by this system id
+    #[arg(long)]
+    pub system: Option<u8>,
+}
+
+#[derive(clap::Args)]
+pub struct DumpArgs {
+    /// Only messages with these names, ex. `--message ATTITUDE,GPS_RAW_INT`
+    #[arg(long, value_delimiter = ',')]
+    pub message: Vec<String>,
+
+    /// Only messages sent by this system id
+    #[arg(long)]
+    pub system: Option<u8>,
+
+    /// Stop after this many messages
+    #[arg(long)]
+    pub count: Option<usize>,
+
+    /// Stop after this many seconds
+    #[arg(long)]
+    pub duration: Option<u64>,
+}
+
+#[derive(Subcommand)]
+pub enum ParamsCommand {
+    /// Print the value of a parameter
+    Get { name: String },
+    /// Change a parameter and wait until the vehicle reports the new value
+    Set {
+        name: String,
+        #[arg(allow_negative_numbers = true)]
+        value: f32,
+    },
+    /// Write all parameters as NAME,VALUE lines
+    Save { path: PathBuf },
+    /// Set parameters from a file of NAME,VALUE lines, unchanged ones are not sent
+    Load { path: PathBuf },
+}
+
+#[derive(Subcommand)]
+pub enum MissionCommand {
+    Download { path: PathBuf },
+    Upload { path: PathBuf },
+}
+
+#[derive(clap::Args)]
+pub struct CmdArgs {
+    /// MAV_CMD name, with or without the MAV_CMD_ prefix, or number
+    pub command: String,
+
+    /// Up to 7 parameters, missing ones are 0
+    #[arg(allow_negative_numbers = true, num_args = 0..=7)]
+    pub params: Vec<f32>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// one JSON object per message
+    Jsonl,
+    /// a file per message type with a column per field
+    Csv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Dialect {
+    Common,
+    Ardupilotmega,
+    Development,
+    /// every message set shipped with the mavlink crate
+    All,
+}
+
+impl Dialect {
+    /// Definitions the message set is generated from
+    pub fn definitions_file(&self) -> &'static str {
+        match self {
+            Dialect::Common => "common.xml",
+            Dialect::Ardupilotmega => "ardupilotmega.xml",
+            Dialect::Development => "development.xml",
+            Dialect::All => "all.xml",
+        }
+    }
 }
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use crate::dialect::DialectMessage;
+use crate::dialect::LOG_MESSAGE_ID_BASE;
+use crate::frame::ReceivedFrame;
+use crate::onboard_log::log_frame;
+use crate::runtime_dialect::FieldDefinition;
+use crate::runtime_dialect::FieldType;
+use crate::runtime_dialect::MessageDefinition;
+
+const HEADER: [u8; 2] = [0xA3, 0x95];
+const HEADER_LEN: usize = 3;
+const FMT_TYPE: u8 = 128;
+/// FMT describes itself, this is its layout
+const FMT_FORMAT: &str = "BBnNZ";
+const FMT_COLUMNS: &str = "Type,Length,Name,Format,Columns";
+const FMT_LEN: usize = 89;
+
+/// Layout of one record type declared by a FMT record
+struct Format {
+    definition: &'static MessageDefinition,
+    len: usize,
+    time_scale: Option<u64>,
+}
+
+/// Reads an ArduPilot DataFlash .bin log, records with unknown types are skipped
+pub fn read<M: DialectMessage>(path: &Path) -> io::Result<Vec<ReceivedFrame<M>>> {
+    let bytes = std::fs::read(path)?;
+    if !bytes.starts_with(&HEADER) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a DataFlash log",
+        ));
+    }
+    let mut formats = HashMap::new();
+    formats.insert(
+        FMT_TYPE,
+        create_format(FMT_TYPE, "FMT", FMT_FORMAT, FMT_COLUMNS, FMT_LEN),
+    );
+    let mut frames = vec![];
+    let mut micros = 0;
+    let mut offset = 0;
+    while offset + HEADER_LEN <= bytes.len() {
+        let record_type = bytes[offset + 2];
+        let format = formats.get(&record_type);
+        let record_end = format.map_or(usize::MAX, |f: &Format| offset + f.len);
+        if !bytes[offset..].starts_with(&HEADER) || record_end > bytes.len() {
+            offset += 1;
+            continue;
+        }
+        let format = format.unwrap();
+        let raw = bytes[offset..record_end].to_vec();
+        let payload = raw[HEADER_LEN..].to_vec();
+        if let Some(time) = format
+            .time_scale
+            .and_then(|scale| read_time(&payload, scale))
+        {
+            micros = time;
+        }
+        let definition = format.definition;
+        if record_type == FMT_TYPE
+            && let Some(declared) = parse_fmt(&payload)
+        {
+            formats.insert(declared.definition.id as u8, declared);
+        }
+        frames.push(log_frame(
+            definition,
+            payload,
+            raw,
+            micros,
+            frames.len() as u8,
+        ));
+        offset = record_end;
+    }
+    Ok(frames)
+}
+
+/// TimeUS is a u64 of microseconds, TimeMS a u32 of milliseconds
+fn read_time(payload: &[u8], scale: u64) -> Option<u64> {
+    let mut bytes = [0u8; 8];
+    let len = if scale == 1 { 8 } else { 4 };
+    bytes[..len].copy_from_slice(payload.get(..len)?);
+    Some(u64::from_le_bytes(bytes) * scale)
+}
+
+/// Format declared by a FMT record, None for formats that cannot be read: shorter than the
+/// record header or than their fields, or redefining FMT itself
+fn parse_fmt(payload: &[u8]) -> Option<Format> {
+    let text = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .take_while(|b| **b != 0)
+            .map(|b| *b as char)
+            .collect::<String>()
+    };
+    let record_type = *payload.first()?;
+    let len = *payload.get(1)? as usize;
+    if record_type == FMT_TYPE || len < HEADER_LEN {
+        return None;
+    }
+    let format = create_format(
+        record_type,
+        &text(payload.get(2..6)?),
+        &text(payload.get(6..22)?),
+        &text(payload.get(22..86)?),
+        len,
+    );
+    (HEADER_LEN + format.definition.encoded_len() <= len).then_some(format)
+}
+
+fn create_format(record_type: u8, name: &str, format: &str, columns: &str, len: usize) -> Format {
+    let columns = columns.split(',').collect::<Vec<_>>();
+    let fields = format
+        .chars()
+        .zip(&columns)
+        .enumerate()
+        .map(|(i, (c, column))| {
+            let (field_type, array_len, units) = field_type(c)?;
+            Some(FieldDefinition {
+                name: column.to_string(),
+                field_type,
+                array_len,
+                enum_name: None,
+                units: units.map(str::to_string),
+                is_bitmask: false,
+                is_extension: false,
+                declaration_index: i,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        // the record is still shown, as bytes, when a format character is unknown
+        .unwrap_or_else(|| {
+            vec![FieldDefinition {
+                name: "data".to_string(),
+                field_type: FieldType::UInt8,
+                array_len: Some(len.saturating_sub(HEADER_LEN)),
+                enum_name: None,
+                units: None,
+                is_bitmask: false,
+                is_extension: false,
+                declaration_index: 0,
+            }]
+        });
+    let time_scale = match (format.chars().next(), columns.first()) {
+        (Some('Q'), Some(&"TimeUS")) => Some(1),
+        (Some('I'), Some(&"TimeMS")) => Some(1000),
+        _ => None,
+    };
+    let definition = MessageDefinition {
+        id: LOG_MESSAGE_ID_BASE + record_type as u32,
+        name: name.to_string(),
+        fields,
+        extra_crc: 0,
+    };
+    Format {
+        definition: Box::leak(Box::new(definition)),
+        len,
+        time_scale,
+    }
+}
+
+/// Type, array length and units of a DataFlash format character
+fn field_type(c: char) -> Option<(FieldType, Option<usize>, Option<&'static str>)> {
+    let field_type = match c {
+        'b' => (FieldType::Int8, None, None),
+        'B' | 'M' => (FieldType::UInt8, None, None),
+        // c, C, e and E hold values multiplied by 100
+        'h' | 'c' => (FieldType::Int16, None, None),
+        'H' | 'C' => (FieldType::UInt16, None, None),
+        'i' | 'e' => (FieldType::Int32, None, None),
+        'I' | 'E' => (FieldType::UInt32, None, None),
+        'L' => (FieldType::Int32, None, Some("degE7")),
+        'q' => (FieldType::Int64, None, None),
+        'Q' => (FieldType::UInt64, None, None),
+        'f' => (FieldType::Float, None, None),
+        'd' => (FieldType::Double, None, None),
+        'n' => (FieldType::Char, Some(4), None),
+        'N' => (FieldType::Char, Some(16), None),
+        'Z' => (FieldType::Char, Some(64), None),
+        'a' => (FieldType::Int16, Some(32), None),
+        _ => return None,
+    };
+    Some(field_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::common::MavMessage;
+
+    use crate::dataflash::FMT_LEN;
+    use crate::dataflash::FMT_TYPE;
+    use crate::dataflash::HEADER;
+    use crate::dataflash::read;
+    use crate::dialect::DialectMessage;
+    use crate::dialect::ExtendedMessage;
+    use crate::frame::FrameContent;
+    use crate::onboard_log::boot_time;
+
+    fn fmt_record(record_type: u8, len: u8, name: &str, format: &str, columns: &str) -> Vec<u8> {
+        let mut record = vec![0xA3, 0x95, FMT_TYPE, record_type, len];
+        for (text, text_len) in [(name, 4), (format, 16), (columns, 64)] {
+            let mut bytes = text.as_bytes().to_vec();
+            bytes.resize(text_len, 0);
+            record.extend(bytes);
+        }
+        assert_eq!(record.len(), FMT_LEN);
+        record
+    }
+
+    #[test]
+    fn test_read_dataflash() {
+        let path = std::env::temp_dir().join("mavlink_debugger_test.bin");
+        let mut bytes = fmt_record(200, 19, "GPS", "QLh", "TimeUS,Lat,Alt");
+        bytes.extend([0xA3, 0x95, 200]);
+        bytes.extend(2_000_000u64.to_le_bytes());
+        bytes.extend(473977419i32.to_le_bytes());
+        bytes.extend(488i16.to_le_bytes());
+        // garbage between records is skipped
+        bytes.extend([0x00, 0xA3]);
+        std::fs::write(&path, &bytes).unwrap();
+
+        let frames = read::<ExtendedMessage<MavMessage>>(&path).unwrap();
+        assert_eq!(frames.len(), 2);
+        let FrameContent::Message { message, .. } = &frames[1].content else {
+            panic!("GPS is declared by FMT");
+        };
+        let fields = message.fields();
+        assert_eq!(fields[0].formatted_value(), "2000000");
+        assert_eq!(fields[1].scaled_value(), Some("47.3977419°".to_string()));
+        assert_eq!(fields[2].name, "Alt");
+        assert_eq!(frames[1].received, boot_time(2_000_000));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_corrupt_dataflash() {
+        let path = std::env::temp_dir().join("mavlink_debugger_test_corrupt.bin");
+        // shorter than the record header, shorter than its fields and redefining FMT
+        let mut bytes = fmt_record(201, 2, "BAD1", "B", "Value");
+        bytes.extend(fmt_record(202, 5, "BAD2", "Q", "TimeUS"));
+        bytes.extend(fmt_record(FMT_TYPE, 10, "FMT", "BB", "Type,Length"));
+        for record_type in [201, 202] {
+            bytes.extend(HEADER);
+            bytes.extend([record_type, 0, 0]);
+        }
+        // FMT keeps its layout
+        bytes.extend(fmt_record(203, 4, "OK", "B", "Value"));
+        bytes.extend(HEADER);
+        bytes.extend([203, 7]);
+        std::fs::write(&path, &bytes).unwrap();
+
+        let frames = read::<ExtendedMessage<MavMessage>>(&path).unwrap();
+        assert_eq!(frames.len(), 5);
+        let FrameContent::Message { message, .. } = &frames[4].content else {
+            panic!("OK is declared by FMT");
+        };
+        assert_eq!(message.fields()[0].formatted_value(), "7");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
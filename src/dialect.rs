@@ -0,0 +1,226 @@
+use std::fmt::Debug;
+use std::fmt::Formatter;
+
+use mavlink::MavlinkVersion;
+use mavlink::Message;
+use mavlink::common;
+use mavlink::error::ParserError;
+use serde::Serialize;
+
+use crate::message_fields::MessageField;
+use crate::message_fields::message_fields;
+use crate::runtime_dialect;
+use crate::runtime_dialect::MessageDefinition;
+use crate::runtime_dialect::RuntimeDialect;
+
+/// Largest payload of a MAVLink 2 frame
+const MAX_PAYLOAD_LEN: usize = 255;
+
+/// Ids of onboard log messages start above every 24 bit MAVLink 2 id
+pub const LOG_MESSAGE_ID_BASE: u32 = 1 << 24;
+
+/// Message set selected with --dialect, extended with --dialect-xml definitions
+pub trait DialectMessage: Message + Clone + Debug + Send + Sync + 'static {
+    /// Decoded fields with their types, enum names and units
+    fn fields(&self) -> Vec<MessageField>;
+    /// Message of a runtime definition, from --dialect-xml or an onboard log
+    fn runtime(definition: &'static MessageDefinition, payload: Vec<u8>) -> Self;
+}
+
+/// Message of a compiled-in dialect or, for ids it does not know, of the runtime dialect
+#[derive(Clone)]
+pub enum ExtendedMessage<D> {
+    Compiled(D),
+    Runtime(RuntimeMessage),
+}
+
+#[derive(Clone)]
+pub struct RuntimeMessage {
+    definition: &'static MessageDefinition,
+    payload: Vec<u8>,
+}
+
+impl<D: Message + Serialize + Clone + Debug + Send + Sync + 'static> DialectMessage
+    for ExtendedMessage<D>
+{
+    fn fields(&self) -> Vec<MessageField> {
+        match self {
+            ExtendedMessage::Compiled(m) => {
+                let fields = message_fields(m);
+                match runtime_dialect::standard() {
+                    Some(d) => d.annotate(m.message_id(), fields),
+                    None => fields,
+                }
+            }
+            ExtendedMessage::Runtime(m) => m.fields(),
+        }
+    }
+
+    fn runtime(definition: &'static MessageDefinition, payload: Vec<u8>) -> Self {
+        ExtendedMessage::Runtime(RuntimeMessage {
+            definition,
+            payload,
+        })
+    }
+}
+
+impl RuntimeMessage {
+    /// Enum names come from --dialect-xml, log definitions have no enums
+    fn fields(&self) -> Vec<MessageField> {
+        match runtime_dialect::installed().or_else(runtime_dialect::standard) {
+            Some(d) => d.decode(self.definition, &self.payload),
+            None => RuntimeDialect::default().decode(self.definition, &self.payload),
+        }
+    }
+}
+
+impl<D: Debug> Debug for ExtendedMessage<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtendedMessage::Compiled(m) => m.fmt(f),
+            ExtendedMessage::Runtime(m) => {
+                let fields = m
+                    .fields()
+                    .iter()
+                    .map(|field| format!("{}: {}", field.name, field.formatted_value()))
+                    .collect::<Vec<_>>();
+                write!(f, "{} {{ {} }}", m.definition.name, fields.join(", "))
+            }
+        }
+    }
+}
+
+impl<D: Message> Message for ExtendedMessage<D> {
+    fn message_id(&self) -> u32 {
+        match self {
+            ExtendedMessage::Compiled(m) => m.message_id(),
+            ExtendedMessage::Runtime(m) => m.definition.id,
+        }
+    }
+
+    fn message_name(&self) -> &'static str {
+        match self {
+            ExtendedMessage::Compiled(m) => m.message_name(),
+            ExtendedMessage::Runtime(m) => m.definition.name.as_str(),
+        }
+    }
+
+    fn ser(&self, version: MavlinkVersion, bytes: &mut [u8]) -> usize {
+        match self {
+            ExtendedMessage::Compiled(m) => m.ser(version, bytes),
+            ExtendedMessage::Runtime(m) => {
+                bytes[..m.payload.len()].copy_from_slice(&m.payload);
+                m.payload.len()
+            }
+        }
+    }
+
+    fn parse(version: MavlinkVersion, msgid: u32, payload: &[u8]) -> Result<Self, ParserError> {
+        match D::parse(version, msgid, payload) {
+            Ok(m) => Ok(ExtendedMessage::Compiled(m)),
+            Err(ParserError::UnknownMessage { id }) => runtime_dialect::installed()
+                .and_then(|d| d.message(id))
+                .map(|definition| {
+                    ExtendedMessage::Runtime(RuntimeMessage {
+                        definition,
+                        payload: payload.to_vec(),
+                    })
+                })
+                .ok_or(ParserError::UnknownMessage { id }),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn message_id_from_name(name: &str) -> Result<u32, &'static str> {
+        D::message_id_from_name(name).or_else(|e| {
+            runtime_dialect::installed()
+                .and_then(|d| d.message_id_from_name(name))
+                .ok_or(e)
+        })
+    }
+
+    fn default_message_from_id(id: u32) -> Result<Self, &'static str> {
+        D::default_message_from_id(id)
+            .map(ExtendedMessage::Compiled)
+            .or_else(|e| {
+                runtime_dialect::installed()
+                    .and_then(|d| d.message(id))
+                    .map(|definition| {
+                        ExtendedMessage::Runtime(RuntimeMessage {
+                            definition,
+                            payload: vec![0; definition.encoded_len()],
+                        })
+                    })
+                    .ok_or(e)
+            })
+    }
+
+    fn extra_crc(id: u32) -> u8 {
+        if D::default_message_from_id(id).is_ok() {
+            return D::extra_crc(id);
+        }
+        runtime_dialect::installed()
+            .and_then(|d| d.message(id))
+            .map_or(0, |m| m.extra_crc)
+    }
+}
+
+/// Re-decodes a message in another dialect, None when that dialect has no such message
+pub fn convert<From: Message, To: Message>(message: &From) -> Option<To> {
+    if message.message_id() >= LOG_MESSAGE_ID_BASE {
+        return None;
+    }
+    let mut payload = [0u8; MAX_PAYLOAD_LEN];
+    let len = message.ser(MavlinkVersion::V2, &mut payload);
+    To::parse(MavlinkVersion::V2, message.message_id(), &payload[..len]).ok()
+}
+
+/// Common view of a dialect message, the debugger logic only handles common messages
+pub fn to_common<M: Message>(message: &M) -> Option<common::MavMessage> {
+    convert(message)
+}
+
+/// Dialect version of a common message built by the debugger
+pub fn from_common<M: Message>(message: &common::MavMessage) -> Option<M> {
+    convert(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::Message;
+    use mavlink::ardupilotmega;
+    use mavlink::common;
+
+    use crate::dialect::from_common;
+    use crate::dialect::to_common;
+
+    #[test]
+    fn test_to_common() {
+        let heartbeat = ardupilotmega::MavMessage::HEARTBEAT(ardupilotmega::HEARTBEAT_DATA {
+            custom_mode: 5,
+            mavtype: ardupilotmega::MavType::MAV_TYPE_QUADROTOR,
+            ..Default::default()
+        });
+        let Some(common::MavMessage::HEARTBEAT(data)) = to_common(&heartbeat) else {
+            panic!("HEARTBEAT is a common message");
+        };
+        assert_eq!(data.custom_mode, 5);
+        assert_eq!(data.mavtype, common::MavType::MAV_TYPE_QUADROTOR);
+
+        let ahrs2 = ardupilotmega::MavMessage::AHRS2(ardupilotmega::AHRS2_DATA::default());
+        assert!(to_common(&ahrs2).is_none());
+    }
+
+    #[test]
+    fn test_from_common() {
+        let command = common::MavMessage::COMMAND_LONG(common::COMMAND_LONG_DATA {
+            param1: 33.0,
+            command: common::MavCmd::MAV_CMD_REQUEST_MESSAGE,
+            target_system: 1,
+            ..Default::default()
+        });
+        let converted = from_common::<ardupilotmega::MavMessage>(&command).unwrap();
+        assert_eq!(converted.message_name(), "COMMAND_LONG");
+        assert_eq!(to_common(&converted), Some(command));
+    }
+}
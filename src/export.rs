@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::SecondsFormat;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use mavlink::MavHeader;
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::cli::ExportArgs;
+use crate::cli::ExportFormat;
+use crate::dialect::DialectMessage;
+use crate::frame::FrameContent;
+use crate::frame::ReceivedFrame;
+use crate::mavlink_client::read_log;
+use crate::message_fields::FieldValue;
+use crate::message_fields::MessageField;
+
+/// Messages to export or forward, an empty filter lets everything through
+#[derive(Clone, Default)]
+pub struct MessageFilter {
+    pub message_names: Vec<String>,
+    /// Never let through, even when listed in `message_names`
+    pub excluded_message_names: Vec<String>,
+    pub system_id: Option<u8>,
+}
+
+impl MessageFilter {
+    pub fn matches<M: DialectMessage>(&self, header: &MavHeader, message: &M) -> bool {
+        let name = message.message_name();
+        (self.message_names.is_empty() || self.message_names.iter().any(|n| n == name))
+            && !self.excluded_message_names.iter().any(|n| n == name)
+            && self.system_id.is_none_or(|id| id == header.system_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.message_names.is_empty()
+            && self.excluded_message_names.is_empty()
+            && self.system_id.is_none()
+    }
+}
+
+/// Export subcommand, reads the whole log before writing
+pub fn export_log<M: DialectMessage>(address: &str, args: &ExportArgs) -> Result<()> {
+    let frames = read_log::<M>(address)
+        .ok_or_else(|| eyre!("Only logs can be exported, use a tlog:, bin: or ulg: address"))??;
+    let filter = MessageFilter {
+        message_names: args.message.clone(),
+        system_id: args.system,
+        ..Default::default()
+    };
+    let written = match args.format {
+        ExportFormat::Jsonl => write_jsonl(&frames, &filter, &args.output)?,
+        ExportFormat::Csv => write_csv(&frames, &filter, &args.output)?,
+    };
+    println!("Exported {} messages to {}", written, args.output.display());
+    Ok(())
+}
+
+/// Decoded messages passing the filter, undecodable frames are left out
+fn filtered<'a, M: DialectMessage>(
+    frames: &'a [ReceivedFrame<M>],
+    filter: &'a MessageFilter,
+) -> impl Iterator<Item = (&'a ReceivedFrame<M>, &'a MavHeader, &'a M)> {
+    frames.iter().filter_map(|frame| match &frame.content {
+        FrameContent::Message { header, message } if filter.matches(header, message) => {
+            Some((frame, header, message))
+        }
+        _ => None,
+    })
+}
+
+fn timestamp<M>(frame: &ReceivedFrame<M>) -> String {
+    frame.received.to_rfc3339_opts(SecondsFormat::Micros, false)
+}
+
+/// Writes one JSON object per line, returns the number of messages written
+pub fn write_jsonl<M: DialectMessage>(
+    frames: &[ReceivedFrame<M>],
+    filter: &MessageFilter,
+    path: &Path,
+) -> io::Result<usize> {
+    let mut file = BufWriter::new(File::create(path)?);
+    let mut written = 0;
+    for (frame, header, message) in filtered(frames, filter) {
+        writeln!(file, "{}", json_line(frame, header, message))?;
+        written += 1;
+    }
+    file.flush()?;
+    Ok(written)
+}
+
+/// Decoded message with its header and timestamp
+pub fn json_line<M: DialectMessage>(
+    frame: &ReceivedFrame<M>,
+    header: &MavHeader,
+    message: &M,
+) -> Value {
+    let fields = message
+        .fields()
+        .into_iter()
+        .map(|field| (field.name, json_value(&field.value)))
+        .collect::<Map<_, _>>();
+    serde_json::json!({
+        "timestamp": timestamp(frame),
+        "system_id": header.system_id,
+        "component_id": header.component_id,
+        "sequence": header.sequence,
+        "message_id": message.message_id(),
+        "message": message.message_name(),
+        "fields": fields,
+    })
+}
+
+fn json_value(value: &FieldValue) -> Value {
+    match value {
+        FieldValue::Signed(v) => Value::from(*v),
+        FieldValue::Unsigned(v) => Value::from(*v),
+        // NaN and infinities have no JSON number and become null
+        FieldValue::Float(v) => Value::from(*v),
+        FieldValue::Text(v) | FieldValue::Enum(v) => Value::from(v.as_str()),
+        FieldValue::Flags(flags) => Value::from(flags.clone()),
+        FieldValue::Array(values) => Value::Array(values.iter().map(json_value).collect()),
+    }
+}
+
+/// Writes a CSV file per message type into the directory, one column per field,
+/// returns the number of messages written
+pub fn write_csv<M: DialectMessage>(
+    frames: &[ReceivedFrame<M>],
+    filter: &MessageFilter,
+    dir: &Path,
+) -> io::Result<usize> {
+    std::fs::create_dir_all(dir)?;
+    let mut files: BTreeMap<&str, BufWriter<File>> = BTreeMap::new();
+    let mut written = 0;
+    for (frame, header, message) in filtered(frames, filter) {
+        let fields = message.fields();
+        let name = message.message_name();
+        if !files.contains_key(name) {
+            let mut file = BufWriter::new(File::create(dir.join(format!("{name}.csv")))?);
+            let columns = ["timestamp", "system_id", "component_id", "sequence"]
+                .into_iter()
+                .chain(fields.iter().map(|field| field.name.as_str()))
+                .map(csv_cell)
+                .collect::<Vec<_>>();
+            writeln!(file, "{}", columns.join(","))?;
+            files.insert(name, file);
+        }
+        let file = files.get_mut(name).unwrap();
+        let cells = [
+            timestamp(frame),
+            header.system_id.to_string(),
+            header.component_id.to_string(),
+            header.sequence.to_string(),
+        ]
+        .into_iter()
+        .chain(fields.iter().map(csv_value))
+        .map(|cell| csv_cell(&cell))
+        .collect::<Vec<_>>();
+        writeln!(file, "{}", cells.join(","))?;
+        written += 1;
+    }
+    for file in files.values_mut() {
+        file.flush()?;
+    }
+    Ok(written)
+}
+
+/// Field value as written to a cell, strings without the quotes of the details view
+fn csv_value(field: &MessageField) -> String {
+    match &field.value {
+        FieldValue::Text(v) => v.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn csv_cell(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use chrono::TimeZone;
+    use mavlink::MavHeader;
+    use mavlink::common::MavMessage;
+    use mavlink::common::PARAM_VALUE_DATA;
+    use mavlink::common::SYSTEM_TIME_DATA;
+
+    use crate::dialect::ExtendedMessage;
+    use crate::export::MessageFilter;
+    use crate::export::csv_cell;
+    use crate::export::write_csv;
+    use crate::export::write_jsonl;
+    use crate::frame::FrameContent;
+    use crate::frame::ReceivedFrame;
+    use crate::signing::SignatureStatus;
+
+    fn frame(system_id: u8, message: MavMessage) -> ReceivedFrame<ExtendedMessage<MavMessage>> {
+        ReceivedFrame {
+            raw: vec![],
+            received: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+            content: FrameContent::Message {
+                header: MavHeader {
+                    system_id,
+                    component_id: 1,
+                    sequence: 7,
+                },
+                message: ExtendedMessage::Compiled(message),
+            },
+            link_id: 0,
+            signature: SignatureStatus::Unsigned,
+        }
+    }
+
+    fn frames() -> Vec<ReceivedFrame<ExtendedMessage<MavMessage>>> {
+        vec![
+            frame(
+                1,
+                MavMessage::SYSTEM_TIME(SYSTEM_TIME_DATA {
+                    time_unix_usec: 1_700_000_000_000_000,
+                    time_boot_ms: 1000,
+                }),
+            ),
+            frame(
+                2,
+                MavMessage::PARAM_VALUE(PARAM_VALUE_DATA {
+                    param_value: 0.5,
+                    ..Default::default()
+                }),
+            ),
+            ReceivedFrame::truncated(vec![0xFD], Local::now()),
+        ]
+    }
+
+    #[test]
+    fn test_write_jsonl() {
+        let path = std::env::temp_dir().join("mavlink_debugger_test_export.jsonl");
+        let filter = MessageFilter {
+            system_id: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(write_jsonl(&frames(), &filter, &path).unwrap(), 1);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let line: serde_json::Value = serde_json::from_str(text.trim_end()).unwrap();
+        assert_eq!(line["message"], "SYSTEM_TIME");
+        assert_eq!(line["sequence"], 7);
+        assert_eq!(line["fields"]["time_boot_ms"], 1000);
+        assert_eq!(
+            line["timestamp"],
+            Local
+                .timestamp_opt(1_700_000_000, 0)
+                .unwrap()
+                .to_rfc3339_opts(chrono::SecondsFormat::Micros, false)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_message_filter() {
+        let frames = frames();
+        let FrameContent::Message { header, message } = &frames[1].content else {
+            unreachable!()
+        };
+        let mut filter = MessageFilter::default();
+        assert!(filter.is_empty() && filter.matches(header, message));
+        filter.message_names = vec!["PARAM_VALUE".to_string()];
+        assert!(filter.matches(header, message));
+        filter.excluded_message_names = vec!["PARAM_VALUE".to_string()];
+        assert!(!filter.matches(header, message));
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let dir = std::env::temp_dir().join("mavlink_debugger_test_export");
+        let _ = std::fs::remove_dir_all(&dir);
+        let filter = MessageFilter {
+            message_names: vec!["PARAM_VALUE".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(write_csv(&frames(), &filter, &dir).unwrap(), 1);
+
+        assert!(!dir.join("SYSTEM_TIME.csv").exists());
+        let text = std::fs::read_to_string(dir.join("PARAM_VALUE.csv")).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("timestamp,system_id,component_id,sequence,param_value,"));
+        assert!(lines[1].contains(",2,1,7,0.5,"));
+        assert_eq!(csv_cell("RATE,\"ROLL\""), "\"RATE,\"\"ROLL\"\"\"");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
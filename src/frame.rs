@@ -0,0 +1,601 @@
+use std::ops::Range;
+
+use chrono::DateTime;
+use chrono::Local;
+use mavlink::MAV_STX;
+use mavlink::MAV_STX_V2;
+use mavlink::MavHeader;
+use mavlink::MavlinkVersion;
+use mavlink::Message;
+use strum::Display;
+use strum::EnumIter;
+
+use crate::dialect::LOG_MESSAGE_ID_BASE;
+use crate::signing::SignatureStatus;
+
+const V1_HEADER_LEN: usize = 6;
+pub const V2_HEADER_LEN: usize = 10;
+pub const CHECKSUM_LEN: usize = 2;
+pub const SIGNATURE_LEN: usize = 13;
+pub const INCOMPAT_FLAG_SIGNED: u8 = 0x01;
+const MAX_PAYLOAD_LEN: usize = 255;
+
+/// Why a frame read from the link could not be turned into a message
+#[derive(Clone, Copy, Debug, Display, EnumIter, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UndecodableReason {
+    #[strum(to_string = "CRC mismatch")]
+    CrcMismatch,
+    #[strum(to_string = "unknown message id")]
+    UnknownMessageId,
+    #[strum(to_string = "truncated frame")]
+    Truncated,
+    #[strum(to_string = "invalid payload")]
+    InvalidPayload,
+}
+
+#[derive(Clone)]
+pub enum FrameContent<M> {
+    Message { header: MavHeader, message: M },
+    Undecodable(UndecodableReason),
+}
+
+/// Bytes of one frame part on the wire, with their meaning
+pub struct FrameField {
+    pub name: &'static str,
+    pub bytes: Range<usize>,
+    pub value: String,
+    /// False for missing or incomplete bytes and for a wrong checksum
+    pub is_valid: bool,
+}
+
+/// Frame exactly as received, with its decoded message when there is one
+#[derive(Clone)]
+pub struct ReceivedFrame<M> {
+    pub raw: Vec<u8>,
+    /// Time the frame arrived, or was recorded when replaying a log
+    pub received: DateTime<Local>,
+    pub content: FrameContent<M>,
+    /// Index of the address the frame arrived on, 0 for logs
+    pub link_id: usize,
+    pub signature: SignatureStatus,
+}
+
+impl<M> ReceivedFrame<M> {
+    pub fn truncated(raw: Vec<u8>, received: DateTime<Local>) -> Self {
+        ReceivedFrame {
+            signature: SignatureStatus::unchecked(&raw),
+            raw,
+            received,
+            content: FrameContent::Undecodable(UndecodableReason::Truncated),
+            link_id: 0,
+        }
+    }
+
+    pub fn is_v2(&self) -> bool {
+        self.raw.first() == Some(&MAV_STX_V2)
+    }
+
+    fn header_len(&self) -> usize {
+        if self.is_v2() {
+            V2_HEADER_LEN
+        } else {
+            V1_HEADER_LEN
+        }
+    }
+
+    /// Payload length declared in the header
+    pub fn payload_len(&self) -> Option<usize> {
+        self.raw.get(1).map(|len| *len as usize)
+    }
+
+    pub fn payload(&self) -> Option<&[u8]> {
+        self.raw
+            .get(self.header_len()..self.header_len() + self.payload_len()?)
+    }
+
+    pub fn message_id(&self) -> Option<u32> {
+        message_id(&self.raw)
+    }
+}
+
+impl<M: Message> ReceivedFrame<M> {
+    /// Record of an onboard log, its bytes are not a MAVLink frame
+    pub fn is_log_record(&self) -> bool {
+        matches!(&self.content, FrameContent::Message { message, .. } if message.message_id() >= LOG_MESSAGE_ID_BASE)
+    }
+
+    /// Wire layout of the frame, parts cut off by truncation have empty byte ranges
+    pub fn fields(&self) -> Vec<FrameField> {
+        let raw = &self.raw;
+        let clip = |range: Range<usize>| range.start.min(raw.len())..range.end.min(raw.len());
+        let byte = |index: usize| raw.get(index).copied();
+        let mut fields = vec![];
+        let mut push = |name, range: Range<usize>, value: Option<String>| {
+            let bytes = clip(range);
+            let is_valid = !bytes.is_empty() && value.is_some();
+            let value = if bytes.is_empty() {
+                "missing".to_string()
+            } else {
+                value.unwrap_or_else(|| "incomplete".to_string())
+            };
+            fields.push(FrameField {
+                name,
+                bytes,
+                value,
+                is_valid,
+            });
+        };
+
+        push(
+            "STX",
+            0..1,
+            Some(if self.is_v2() {
+                "MAVLink 2".to_string()
+            } else {
+                "MAVLink 1".to_string()
+            }),
+        );
+        push("LEN", 1..2, byte(1).map(|len| len.to_string()));
+        let ids_start = if self.is_v2() {
+            let incompat_flags = byte(2);
+            push(
+                "INCOMPAT",
+                2..3,
+                incompat_flags.map(|flags| {
+                    if flags & INCOMPAT_FLAG_SIGNED != 0 {
+                        format!("0x{:02x} signed", flags)
+                    } else {
+                        format!("0x{:02x}", flags)
+                    }
+                }),
+            );
+            push(
+                "COMPAT",
+                3..4,
+                byte(3).map(|flags| format!("0x{:02x}", flags)),
+            );
+            4
+        } else {
+            2
+        };
+        push(
+            "SEQ",
+            ids_start..ids_start + 1,
+            byte(ids_start).map(|b| b.to_string()),
+        );
+        push(
+            "SYSID",
+            ids_start + 1..ids_start + 2,
+            byte(ids_start + 1).map(|b| b.to_string()),
+        );
+        push(
+            "COMPID",
+            ids_start + 2..ids_start + 3,
+            byte(ids_start + 2).map(|b| b.to_string()),
+        );
+        let message_id = self.message_id();
+        push(
+            "MSGID",
+            ids_start + 3..self.header_len(),
+            message_id.map(|id| match M::default_message_from_id(id) {
+                Ok(message) => format!("{} {}", id, message.message_name()),
+                Err(_) => format!("{} unknown", id),
+            }),
+        );
+
+        let payload_len = self.payload_len().unwrap_or_default();
+        let payload_end = self.header_len() + payload_len;
+        let payload = clip(self.header_len()..payload_end);
+        let full_len = message_id
+            .and_then(|id| M::default_message_from_id(id).ok())
+            .map(|message| {
+                let mut bytes = [0u8; MAX_PAYLOAD_LEN];
+                // MAVLink 1 serialization keeps trailing zeros
+                message.ser(MavlinkVersion::V1, &mut bytes)
+            });
+        let payload_value = match full_len {
+            Some(full_len) if full_len > payload_len => format!(
+                "{} bytes, {} trailing zero bytes truncated",
+                payload_len,
+                full_len - payload_len
+            ),
+            _ => format!("{} bytes", payload_len),
+        };
+        push(
+            "PAYLOAD",
+            payload.clone(),
+            (payload.len() == payload_len).then_some(payload_value),
+        );
+
+        let checksum = raw
+            .get(payload_end..payload_end + CHECKSUM_LEN)
+            .map(|crc| u16::from_le_bytes([crc[0], crc[1]]));
+        let expected_checksum = message_id
+            .filter(|id| M::default_message_from_id(*id).is_ok() && raw.len() >= payload_end)
+            .map(|id| mavlink::calculate_crc(&raw[1..payload_end], M::extra_crc(id)));
+        push(
+            "CRC",
+            payload_end..payload_end + CHECKSUM_LEN,
+            checksum.map(|crc| match expected_checksum {
+                Some(expected) if expected != crc => {
+                    format!("0x{:04x}, expected 0x{:04x}", crc, expected)
+                }
+                Some(_) => format!("0x{:04x} ok", crc),
+                None => format!("0x{:04x}", crc),
+            }),
+        );
+
+        if is_signed(raw) {
+            let start = payload_end + CHECKSUM_LEN;
+            push(
+                "LINK ID",
+                start..start + 1,
+                byte(start).map(|b| b.to_string()),
+            );
+            push(
+                "TIMESTAMP",
+                start + 1..start + 7,
+                raw.get(start + 1..start + 7).map(|t| {
+                    let mut timestamp = [0u8; 8];
+                    timestamp[..6].copy_from_slice(t);
+                    format!("{} x 10us since 2015", u64::from_le_bytes(timestamp))
+                }),
+            );
+            push(
+                "SIGNATURE",
+                start + 7..start + SIGNATURE_LEN,
+                raw.get(start + 7..start + SIGNATURE_LEN)
+                    .map(|_| format!("6 bytes, {}", self.signature)),
+            );
+            if self.signature.is_rejected() {
+                for field in fields.iter_mut().filter(|f| f.name == "SIGNATURE") {
+                    field.is_valid = false;
+                }
+            }
+        }
+        if checksum
+            .zip(expected_checksum)
+            .is_some_and(|(crc, expected)| crc != expected)
+        {
+            for field in fields.iter_mut().filter(|f| f.name == "CRC") {
+                field.is_valid = false;
+            }
+        }
+        fields
+    }
+}
+
+/// Splits received bytes into frames, bytes outside of frames are dropped
+#[derive(Default)]
+pub struct FrameParser {
+    buffer: Vec<u8>,
+}
+
+impl FrameParser {
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Next frame, a frame the dialect cannot verify is taken whole unless a verified frame
+    /// starts and ends inside it: then its start byte was a stray one and is dropped with the bytes
+    /// before the verified frame
+    pub fn next_frame<M: Message>(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let start = self
+                .buffer
+                .iter()
+                .position(|b| *b == MAV_STX || *b == MAV_STX_V2)
+                .unwrap_or(self.buffer.len());
+            self.buffer.drain(..start);
+            let frame_len = expected_frame_len(&self.buffer)?;
+            if self.buffer.len() < frame_len {
+                return None;
+            }
+            if is_verified::<M>(&self.buffer) {
+                return Some(self.buffer.drain(..frame_len).collect());
+            }
+            // only frames that end inside this one can tell, nothing is waited for
+            let inner_start = (1..frame_len).find(|i| {
+                matches!(self.buffer[*i], MAV_STX | MAV_STX_V2)
+                    && is_verified::<M>(&self.buffer[*i..frame_len])
+            });
+            match inner_start {
+                Some(i) => {
+                    self.buffer.drain(..i);
+                }
+                None => return Some(self.buffer.drain(..frame_len).collect()),
+            }
+        }
+    }
+
+    /// Takes the started frame when the rest of it can no longer arrive
+    pub fn take_incomplete(&mut self) -> Option<Vec<u8>> {
+        let incomplete = std::mem::take(&mut self.buffer);
+        let is_frame_start = incomplete
+            .first()
+            .is_some_and(|b| *b == MAV_STX || *b == MAV_STX_V2);
+        is_frame_start.then_some(incomplete)
+    }
+}
+
+/// Length of the frame starting at the first byte, once its header is there
+pub fn expected_frame_len(bytes: &[u8]) -> Option<usize> {
+    let payload_len = *bytes.get(1)? as usize;
+    match bytes[0] {
+        MAV_STX_V2 => {
+            let is_signed = bytes.get(2)? & INCOMPAT_FLAG_SIGNED != 0;
+            let signature_len = if is_signed { SIGNATURE_LEN } else { 0 };
+            Some(V2_HEADER_LEN + payload_len + CHECKSUM_LEN + signature_len)
+        }
+        _ => Some(V1_HEADER_LEN + payload_len + CHECKSUM_LEN),
+    }
+}
+
+/// Complete frame at the start of the bytes of a message the dialect knows, with a matching checksum
+fn is_verified<M: Message>(bytes: &[u8]) -> bool {
+    if expected_frame_len(bytes).is_none_or(|len| len > bytes.len()) {
+        return false;
+    }
+    let (header_len, message_id) = if bytes[0] == MAV_STX_V2 {
+        (
+            V2_HEADER_LEN,
+            u32::from_le_bytes([bytes[7], bytes[8], bytes[9], 0]),
+        )
+    } else {
+        (V1_HEADER_LEN, bytes[5] as u32)
+    };
+    let payload_end = header_len + bytes[1] as usize;
+    let checksum = u16::from_le_bytes([bytes[payload_end], bytes[payload_end + 1]]);
+    M::default_message_from_id(message_id).is_ok()
+        && mavlink::calculate_crc(&bytes[1..payload_end], M::extra_crc(message_id)) == checksum
+}
+
+/// Message id declared in the header of a frame
+pub fn message_id(raw: &[u8]) -> Option<u32> {
+    if raw.first() == Some(&MAV_STX_V2) {
+        raw.get(7..10)
+            .map(|id| u32::from_le_bytes([id[0], id[1], id[2], 0]))
+    } else {
+        raw.get(5).map(|id| *id as u32)
+    }
+}
+
+/// MAVLink 2 frame with the signed incompatibility flag
+pub fn is_signed(raw: &[u8]) -> bool {
+    raw.first() == Some(&MAV_STX_V2) && raw.get(2).is_some_and(|f| f & INCOMPAT_FLAG_SIGNED != 0)
+}
+
+/// Decodes a complete frame from the parser with the selected dialect
+pub fn decode_frame<M: Message>(raw: Vec<u8>, received: DateTime<Local>) -> ReceivedFrame<M> {
+    let signature = SignatureStatus::unchecked(&raw);
+    let undecodable = |raw, reason| ReceivedFrame {
+        raw,
+        received,
+        content: FrameContent::Undecodable(reason),
+        link_id: 0,
+        signature,
+    };
+    let frame = ReceivedFrame::<M>::truncated(raw, received);
+    if expected_frame_len(&frame.raw).is_none_or(|len| len > frame.raw.len()) {
+        return frame;
+    }
+    let (Some(message_id), Some(payload_len)) = (frame.message_id(), frame.payload_len()) else {
+        return frame;
+    };
+    // the checksum cannot be verified without the CRC_EXTRA of the message
+    if M::default_message_from_id(message_id).is_err() {
+        return undecodable(frame.raw, UndecodableReason::UnknownMessageId);
+    }
+    let payload_end = frame.header_len() + payload_len;
+    let checksum = u16::from_le_bytes([frame.raw[payload_end], frame.raw[payload_end + 1]]);
+    if mavlink::calculate_crc(&frame.raw[1..payload_end], M::extra_crc(message_id)) != checksum {
+        return undecodable(frame.raw, UndecodableReason::CrcMismatch);
+    }
+
+    let (version, header) = if frame.is_v2() {
+        (MavlinkVersion::V2, &frame.raw[4..7])
+    } else {
+        (MavlinkVersion::V1, &frame.raw[2..5])
+    };
+    let header = MavHeader {
+        sequence: header[0],
+        system_id: header[1],
+        component_id: header[2],
+    };
+    match M::parse(
+        version,
+        message_id,
+        &frame.raw[frame.header_len()..payload_end],
+    ) {
+        Ok(message) => ReceivedFrame {
+            content: FrameContent::Message { header, message },
+            raw: frame.raw,
+            received,
+            link_id: 0,
+            signature,
+        },
+        Err(_) => undecodable(frame.raw, UndecodableReason::InvalidPayload),
+    }
+}
+
+/// Offset, hex and printable characters, 16 bytes per line
+pub fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex = chunk
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let text = chunk
+                .iter()
+                .map(|b| {
+                    if b.is_ascii_graphic() {
+                        *b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            format!("{:04x}  {:<47}  {}", i * 16, hex, text)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use mavlink::MavHeader;
+    use mavlink::MavlinkVersion;
+    use mavlink::common::HEARTBEAT_DATA;
+    use mavlink::common::MavMessage;
+
+    use crate::frame::FrameContent;
+    use crate::frame::FrameParser;
+    use crate::frame::ReceivedFrame;
+    use crate::frame::UndecodableReason;
+    use crate::frame::decode_frame;
+    use crate::frame::hex_dump;
+
+    fn heartbeat_frame(version: MavlinkVersion) -> Vec<u8> {
+        let mut bytes = vec![];
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 7,
+        };
+        let message = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        mavlink::write_versioned_msg(&mut bytes, version, header, &message).unwrap();
+        bytes
+    }
+
+    fn reason(raw: Vec<u8>) -> Option<UndecodableReason> {
+        match decode_frame::<MavMessage>(raw, Local::now()).content {
+            FrameContent::Message { .. } => None,
+            FrameContent::Undecodable(reason) => Some(reason),
+        }
+    }
+
+    #[test]
+    fn test_parser_splits_frames() {
+        let mut parser = FrameParser::default();
+        let v2 = heartbeat_frame(MavlinkVersion::V2);
+        let v1 = heartbeat_frame(MavlinkVersion::V1);
+        parser.push(&[0x00, 0x42]);
+        parser.push(&v2);
+        parser.push(&v1[..4]);
+        assert_eq!(parser.next_frame::<MavMessage>(), Some(v2));
+        assert_eq!(parser.next_frame::<MavMessage>(), None);
+        parser.push(&v1[4..]);
+        assert_eq!(parser.next_frame::<MavMessage>(), Some(v1));
+        assert_eq!(parser.take_incomplete(), None);
+    }
+
+    #[test]
+    fn test_parser_skips_stray_start_bytes() {
+        let mut parser = FrameParser::default();
+        let v2 = heartbeat_frame(MavlinkVersion::V2);
+        // the stray byte declares a signed frame with a payload as long as the next start byte
+        parser.push(&[0xFD]);
+        for _ in 0..20 {
+            parser.push(&v2);
+        }
+        let mut corrupted = v2.clone();
+        corrupted[12] ^= 0xFF;
+        parser.push(&corrupted);
+        parser.push(&v2);
+
+        let mut frames = vec![];
+        while let Some(frame) = parser.next_frame::<MavMessage>() {
+            frames.push(frame);
+        }
+        assert_eq!(frames.len(), 22);
+        assert!(frames[..20].iter().all(|frame| *frame == v2));
+        // a corrupted frame without a frame inside stays whole
+        assert_eq!(frames[20], corrupted);
+        assert_eq!(frames[21], v2);
+    }
+
+    #[test]
+    fn test_parser_keeps_unknown_frames_whole() {
+        let mut parser = FrameParser::default();
+        let mut unknown = heartbeat_frame(MavlinkVersion::V2);
+        unknown[7] = 0xFF;
+        unknown[8] = 0xFF;
+        // a start byte with a length running past the buffer inside the payload
+        unknown[10] = 0xFD;
+        unknown[11] = 0xF0;
+        parser.push(&unknown);
+        assert_eq!(parser.next_frame::<MavMessage>(), Some(unknown.clone()));
+        assert_eq!(parser.take_incomplete(), None);
+        assert_eq!(reason(unknown), Some(UndecodableReason::UnknownMessageId));
+    }
+
+    #[test]
+    fn test_decode_frame() {
+        let frame = decode_frame::<MavMessage>(heartbeat_frame(MavlinkVersion::V2), Local::now());
+        let FrameContent::Message { header, message } = frame.content else {
+            panic!("valid frame is decoded");
+        };
+        assert_eq!(header.sequence, 7);
+        assert!(matches!(message, MavMessage::HEARTBEAT(_)));
+        assert_eq!(reason(heartbeat_frame(MavlinkVersion::V1)), None);
+
+        let mut corrupted = heartbeat_frame(MavlinkVersion::V2);
+        corrupted[12] ^= 0xFF;
+        assert_eq!(reason(corrupted), Some(UndecodableReason::CrcMismatch));
+
+        let mut unknown = heartbeat_frame(MavlinkVersion::V2);
+        unknown[7] = 0xFF;
+        unknown[8] = 0xFF;
+        assert_eq!(reason(unknown), Some(UndecodableReason::UnknownMessageId));
+    }
+
+    #[test]
+    fn test_truncated_frame() {
+        let mut parser = FrameParser::default();
+        let frame = heartbeat_frame(MavlinkVersion::V2);
+        parser.push(&frame[..8]);
+        assert_eq!(parser.next_frame::<MavMessage>(), None);
+        assert_eq!(parser.take_incomplete(), Some(frame[..8].to_vec()));
+    }
+
+    #[test]
+    fn test_frame_fields() {
+        let frame = decode_frame::<MavMessage>(heartbeat_frame(MavlinkVersion::V2), Local::now());
+        let fields = frame.fields();
+        let names = fields.iter().map(|f| f.name).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "STX", "LEN", "INCOMPAT", "COMPAT", "SEQ", "SYSID", "COMPID", "MSGID", "PAYLOAD",
+                "CRC"
+            ]
+        );
+        assert_eq!(fields[7].value, "0 HEARTBEAT");
+        // default heartbeat ends with a zero mavlink_version and system_status
+        assert_eq!(fields[8].bytes, 10..17);
+        assert_eq!(fields[8].value, "7 bytes, 2 trailing zero bytes truncated");
+        assert!(fields[9].value.ends_with("ok"));
+
+        let mut raw = heartbeat_frame(MavlinkVersion::V1);
+        raw.truncate(9);
+        let fields = ReceivedFrame::<MavMessage>::truncated(raw, Local::now()).fields();
+        assert_eq!(fields[6].name, "PAYLOAD");
+        assert_eq!(fields[6].bytes, 6..9);
+        assert_eq!(fields[6].value, "incomplete");
+        assert_eq!(fields[7].value, "missing");
+        assert!(!fields[7].is_valid);
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let lines = hex_dump(b"MAVLink\x00\x01\x02\x03\x04\x05\x06\x07\x08\xfd");
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("0000  4d 41 56 4c 69 6e 6b 00"));
+        assert!(lines[0].ends_with("MAVLink........."));
+        assert_eq!(lines[1], format!("0010  {:<47}  .", "fd"));
+    }
+}
@@ -0,0 +1,671 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::Duration;
+use std::time::Instant;
+
+use mavlink::MavHeader;
+use mavlink::common::MISSION_ITEM_INT_DATA;
+use mavlink::common::MavCmd;
+use mavlink::common::MavMessage;
+use mavlink::common::MavMissionResult;
+use mavlink::common::MavResult;
+use mavlink::common::PARAM_VALUE_DATA;
+use mavlink::error::MessageWriteError;
+use num_traits::FromPrimitive;
+use serde_json::Value;
+use serde_json::json;
+
+use crate::AppEvent;
+use crate::AppState;
+use crate::cli::Args;
+use crate::cli::CmdArgs;
+use crate::cli::Command;
+use crate::cli::DumpArgs;
+use crate::cli::MissionCommand;
+use crate::cli::ParamsCommand;
+use crate::dialect;
+use crate::dialect::DialectMessage;
+use crate::export;
+use crate::export::MessageFilter;
+use crate::frame::FrameContent;
+use crate::frame::ReceivedFrame;
+use crate::handle_frame;
+use crate::mavlink_client;
+use crate::runtime_dialect;
+use crate::signing::Signing;
+use crate::utils::mavlink::decode_param_id;
+use crate::waypoints;
+
+/// Exit codes on top of 1 for errors and 2 for invalid arguments
+const EXIT_TIMEOUT: u8 = 3;
+const EXIT_REJECTED: u8 = 4;
+/// Requests without an answer are sent again after this long
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+/// Missing parameters requested one by one each retry, so the link is not flooded
+const MAX_PARAMETER_REQUESTS: usize = 20;
+
+/// Why a command failed, printed to stderr
+struct Failure {
+    exit_code: u8,
+    message: String,
+}
+
+impl Failure {
+    fn error(message: impl Into<String>) -> Self {
+        Failure {
+            exit_code: 1,
+            message: message.into(),
+        }
+    }
+    fn timeout(message: impl Into<String>) -> Self {
+        Failure {
+            exit_code: EXIT_TIMEOUT,
+            message: message.into(),
+        }
+    }
+    fn rejected(message: impl Into<String>) -> Self {
+        Failure {
+            exit_code: EXIT_REJECTED,
+            message: message.into(),
+        }
+    }
+}
+
+/// A closed link ends the command instead of waiting for answers that cannot come
+impl From<MessageWriteError> for Failure {
+    fn from(e: MessageWriteError) -> Self {
+        Failure::error(format!("Could not send: {}", e))
+    }
+}
+
+/// Runs a subcommand without the interface, results are printed to stdout as JSON Lines
+pub fn run<M: DialectMessage>(args: Args, command: Command) -> ExitCode {
+    let result = match command {
+        Command::Export(export_args) => export::export_log::<M>(&args.addresses[0], &export_args)
+            .map_err(|e| Failure::error(e.to_string())),
+        command => Session::<M>::connect(args).and_then(|mut session| session.run(command)),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("{}", failure.message);
+            ExitCode::from(failure.exit_code)
+        }
+    }
+}
+
+/// Link to the vehicle with the state the interface would show
+struct Session<M: DialectMessage> {
+    app_state: AppState<M>,
+    rx: mpsc::Receiver<AppEvent<M>>,
+    timeout: Duration,
+}
+
+impl<M: DialectMessage> Session<M> {
+    fn connect(args: Args) -> Result<Self, Failure> {
+        if args
+            .addresses
+            .iter()
+            .any(|a| mavlink_client::is_log_address(a))
+        {
+            return Err(Failure::error(
+                "Only export works with logs, other commands need a link to a vehicle",
+            ));
+        }
+        let (tx, rx) = mpsc::channel();
+        let source_details = args.source_details();
+        let signing = Signing::load(
+            args.signing_key.as_deref(),
+            args.signing_passphrase.as_deref(),
+            args.signing_link_id,
+        )
+        .map_err(|e| Failure::error(e.to_string()))?;
+        let vehicle = mavlink_client::connect(
+            &args.addresses,
+            args.forward_filter(),
+            signing.map(Arc::new),
+            source_details,
+            tx,
+        )
+        .map_err(|e| Failure::error(e.to_string()))?;
+        let timeout = Duration::from_secs(args.timeout);
+        Ok(Session {
+            app_state: AppState::default(args, vehicle),
+            rx,
+            timeout,
+        })
+    }
+
+    fn run(&mut self, command: Command) -> Result<(), Failure> {
+        match command {
+            Command::Export(_) => unreachable!("export does not need a link"),
+            Command::Dump(args) => self.dump(&args),
+            Command::Params(ParamsCommand::Get { name }) => {
+                let parameter = self.read_parameter(&name)?;
+                println!("{}", parameter_json(&parameter));
+                Ok(())
+            }
+            Command::Params(ParamsCommand::Set { name, value }) => {
+                let parameter = self.read_parameter(&name)?;
+                let parameter = self.write_parameter(&parameter, value)?;
+                println!("{}", parameter_json(&parameter));
+                Ok(())
+            }
+            Command::Params(ParamsCommand::Save { path }) => self.save_parameters(&path),
+            Command::Params(ParamsCommand::Load { path }) => self.load_parameters(&path),
+            Command::Mission(MissionCommand::Download { path }) => self.download_mission(&path),
+            Command::Mission(MissionCommand::Upload { path }) => self.upload_mission(&path),
+            Command::Cmd(args) => self.send_command(&args),
+        }
+    }
+
+    /// Waits for the next frame until the deadline, the state is updated with it,
+    /// frames with rejected signatures are skipped
+    fn next_frame(&mut self, deadline: Instant) -> Result<Option<ReceivedFrame<M>>, Failure> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match self.rx.recv_timeout(timeout) {
+            Ok(AppEvent::Mavlink(frame)) => {
+                handle_frame(&mut self.app_state, (*frame).clone());
+                Ok((!frame.signature.is_rejected()).then_some(*frame))
+            }
+            Ok(_) | Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(Failure::error("Link closed")),
+        }
+    }
+
+    /// Calls `send` every second until `check` finds the answer in a message
+    fn wait_for<T>(
+        &mut self,
+        what: &str,
+        mut send: impl FnMut(&mut AppState<M>) -> Result<(), MessageWriteError>,
+        mut check: impl FnMut(&AppState<M>, &MavHeader, &MavMessage) -> Option<T>,
+    ) -> Result<T, Failure> {
+        let deadline = Instant::now() + self.timeout;
+        let mut next_send = Instant::now();
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Failure::timeout(format!(
+                    "No {} within {} s",
+                    what,
+                    self.timeout.as_secs()
+                )));
+            }
+            if now >= next_send {
+                send(&mut self.app_state)?;
+                next_send = now + RETRY_INTERVAL;
+            }
+            let Some(frame) = self.next_frame(deadline.min(next_send))? else {
+                continue;
+            };
+            if let FrameContent::Message { header, message } = &frame.content
+                && let Some(message) = dialect::to_common(message)
+                && let Some(answer) = check(&self.app_state, header, &message)
+            {
+                return Ok(answer);
+            }
+        }
+    }
+
+    /// The target is chosen from heartbeats unless --target-system and --target-component are given
+    fn wait_for_target(&mut self) -> Result<(), Failure> {
+        if self.app_state.vehicle.target_details.is_some() {
+            return Ok(());
+        }
+        self.wait_for(
+            "autopilot heartbeat",
+            |_| Ok(()),
+            |app_state, _, _| app_state.vehicle.target_details.clone(),
+        )?;
+        Ok(())
+    }
+
+    fn dump(&mut self, args: &DumpArgs) -> Result<(), Failure> {
+        let filter = MessageFilter {
+            message_names: args.message.clone(),
+            system_id: args.system,
+            ..Default::default()
+        };
+        let end = args
+            .duration
+            .map(|seconds| Instant::now() + Duration::from_secs(seconds));
+        let mut printed = 0;
+        let mut last_received = Instant::now();
+        while args.count.is_none_or(|count| printed < count) {
+            let silence_deadline = last_received + self.timeout;
+            if end.is_some_and(|end| Instant::now() >= end) {
+                break;
+            }
+            if Instant::now() >= silence_deadline {
+                return Err(Failure::timeout(format!(
+                    "No message within {} s",
+                    self.timeout.as_secs()
+                )));
+            }
+            let deadline = end.map_or(silence_deadline, |end| end.min(silence_deadline));
+            let Some(frame) = self.next_frame(deadline)? else {
+                continue;
+            };
+            last_received = Instant::now();
+            if let FrameContent::Message { header, message } = &frame.content
+                && filter.matches(header, message)
+            {
+                println!("{}", export::json_line(&frame, header, message));
+                printed += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_parameter(&mut self, name: &str) -> Result<PARAM_VALUE_DATA, Failure> {
+        self.wait_for_target()?;
+        self.wait_for(
+            &format!("value of {}", name),
+            |app_state| mavlink_client::request_parameter(&app_state.vehicle, name),
+            |app_state, header, message| match message {
+                MavMessage::PARAM_VALUE(data)
+                    if is_from_target(app_state, header)
+                        && decode_param_id(&data.param_id) == name =>
+                {
+                    Some(data.clone())
+                }
+                _ => None,
+            },
+        )
+    }
+
+    /// Sets a parameter and checks the value the vehicle reports back
+    fn write_parameter(
+        &mut self,
+        parameter: &PARAM_VALUE_DATA,
+        value: f32,
+    ) -> Result<PARAM_VALUE_DATA, Failure> {
+        let name = decode_param_id(&parameter.param_id);
+        let reported = self.wait_for(
+            &format!("new value of {}", name),
+            |app_state| {
+                mavlink_client::set_parameter(
+                    &app_state.vehicle,
+                    &name,
+                    value,
+                    parameter.param_type,
+                )
+            },
+            |app_state, header, message| match message {
+                MavMessage::PARAM_VALUE(data)
+                    if is_from_target(app_state, header)
+                        && decode_param_id(&data.param_id) == name =>
+                {
+                    Some(data.clone())
+                }
+                _ => None,
+            },
+        )?;
+        if !is_same_value(reported.param_value, value) {
+            return Err(Failure::rejected(format!(
+                "{} is {} after setting {}",
+                name, reported.param_value, value
+            )));
+        }
+        Ok(reported)
+    }
+
+    /// Whole parameter list of the target, parameters lost on the way are requested one by one
+    fn read_all_parameters(&mut self) -> Result<Vec<PARAM_VALUE_DATA>, Failure> {
+        self.wait_for_target()?;
+        let last_received = Cell::new(None);
+        self.wait_for(
+            "complete parameter list",
+            |app_state| {
+                let parameters = target_parameters(app_state);
+                let Some(count) = parameters.values().next().map(|p| p.param_count) else {
+                    return mavlink_client::request_parameters(&mut app_state.vehicle);
+                };
+                if last_received.replace(Some(parameters.len())) != Some(parameters.len()) {
+                    return Ok(());
+                }
+                (0..count)
+                    .filter(|index| !parameters.contains_key(index))
+                    .take(MAX_PARAMETER_REQUESTS)
+                    .try_for_each(|index| {
+                        mavlink_client::request_parameter_by_index(&app_state.vehicle, index)
+                    })
+            },
+            |app_state, _, _| {
+                let parameters = target_parameters(app_state);
+                parameters
+                    .values()
+                    .next()
+                    .is_some_and(|p| p.param_count as usize == parameters.len())
+                    .then(|| parameters.into_values().collect())
+            },
+        )
+    }
+
+    fn save_parameters(&mut self, path: &Path) -> Result<(), Failure> {
+        let parameters = self.read_all_parameters()?;
+        std::fs::write(path, format_parameter_file(&parameters))
+            .map_err(|e| Failure::error(format!("Could not write {}: {}", path.display(), e)))?;
+        println!(
+            "{}",
+            json!({"path": path.display().to_string(), "parameters": parameters.len()})
+        );
+        Ok(())
+    }
+
+    fn load_parameters(&mut self, path: &Path) -> Result<(), Failure> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Failure::error(format!("Could not read {}: {}", path.display(), e)))?;
+        let values = parse_parameter_file(&text).map_err(Failure::error)?;
+        let parameters = self
+            .read_all_parameters()?
+            .into_iter()
+            .map(|p| (decode_param_id(&p.param_id), p))
+            .collect::<BTreeMap<_, _>>();
+        let mut not_set = vec![];
+        for (name, value) in values {
+            let Some(parameter) = parameters.get(&name) else {
+                eprintln!("Unknown parameter {}", name);
+                not_set.push(name);
+                continue;
+            };
+            if is_same_value(parameter.param_value, value) {
+                continue;
+            }
+            match self.write_parameter(parameter, value) {
+                Ok(reported) => println!(
+                    "{}",
+                    json!({
+                        "name": name,
+                        "value": float_json(reported.param_value),
+                        "previous": float_json(parameter.param_value),
+                    })
+                ),
+                Err(failure) => {
+                    eprintln!("{}", failure.message);
+                    not_set.push(name);
+                }
+            }
+        }
+        if !not_set.is_empty() {
+            return Err(Failure::rejected(format!(
+                "Not set: {}",
+                not_set.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    fn download_mission(&mut self, path: &Path) -> Result<(), Failure> {
+        self.wait_for_target()?;
+        let items = self.wait_for(
+            "complete mission",
+            |app_state| {
+                let Some(target) = app_state.vehicle.target_details.clone() else {
+                    return Ok(());
+                };
+                let is_count_known = app_state.vehicle.target_component().is_some_and(|c| {
+                    let details = c.mission_details.lock().unwrap();
+                    details.mission_items_to_load_num.is_some()
+                });
+                if is_count_known {
+                    mavlink_client::synchronise_mission_items(
+                        &app_state.vehicle,
+                        (target.target_system_id, target.target_component_id),
+                    )
+                } else {
+                    mavlink_client::request_mission_count(&mut app_state.vehicle)
+                }
+            },
+            |app_state, _, _| {
+                let component = app_state.vehicle.target_component()?;
+                let details = component.mission_details.lock().unwrap();
+                let mut items = details.mission_messages.clone();
+                items.dedup_by_key(|item| item.seq);
+                (Some(items.len() as u16) == details.mission_items_to_load_num).then_some(items)
+            },
+        )?;
+        mavlink_client::send_mission_ack(&self.app_state.vehicle)?;
+        std::fs::write(path, waypoints::write(&items))
+            .map_err(|e| Failure::error(format!("Could not write {}: {}", path.display(), e)))?;
+        println!(
+            "{}",
+            json!({"path": path.display().to_string(), "items": items.len()})
+        );
+        Ok(())
+    }
+
+    fn upload_mission(&mut self, path: &Path) -> Result<(), Failure> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Failure::error(format!("Could not read {}: {}", path.display(), e)))?;
+        let items = waypoints::parse(&text).map_err(Failure::error)?;
+        self.wait_for_target()?;
+        let count = items.len() as u16;
+        let is_started = Cell::new(false);
+        loop {
+            // the count is sent again until the vehicle asks for the first item
+            let answer = self.wait_for(
+                "mission item request",
+                |app_state| {
+                    if is_started.get() {
+                        return Ok(());
+                    }
+                    mavlink_client::send_mission_count(&app_state.vehicle, count)
+                },
+                |app_state, header, message| match message {
+                    MavMessage::MISSION_REQUEST_INT(data) if is_from_target(app_state, header) => {
+                        Some(Ok(data.seq))
+                    }
+                    MavMessage::MISSION_REQUEST(data) if is_from_target(app_state, header) => {
+                        Some(Ok(data.seq))
+                    }
+                    MavMessage::MISSION_ACK(data) if is_from_target(app_state, header) => {
+                        Some(Err(data.mavtype))
+                    }
+                    _ => None,
+                },
+            )?;
+            match answer {
+                Ok(seq) => {
+                    is_started.set(true);
+                    let item = items.get(seq as usize).ok_or_else(|| {
+                        Failure::rejected(format!("Vehicle asked for missing item {}", seq))
+                    })?;
+                    mavlink_client::send_mission_item(
+                        &self.app_state.vehicle,
+                        &MISSION_ITEM_INT_DATA {
+                            seq,
+                            ..item.clone()
+                        },
+                    )?;
+                }
+                Err(MavMissionResult::MAV_MISSION_ACCEPTED) => break,
+                Err(result) => {
+                    return Err(Failure::rejected(format!(
+                        "Mission not accepted: {:?}",
+                        result
+                    )));
+                }
+            }
+        }
+        println!(
+            "{}",
+            json!({"path": path.display().to_string(), "items": items.len()})
+        );
+        Ok(())
+    }
+
+    fn send_command(&mut self, args: &CmdArgs) -> Result<(), Failure> {
+        let command = parse_command(&args.command)?;
+        let mut params = [0.0; 7];
+        params[..args.params.len()].copy_from_slice(&args.params);
+        self.wait_for_target()?;
+        // resent commands count up their confirmation, as the MAVLink command protocol asks
+        let mut confirmation = 0u8;
+        let result = self.wait_for(
+            &format!("acknowledgement of {:?}", command),
+            |app_state| {
+                let sent = mavlink_client::send_command_long(
+                    &app_state.vehicle,
+                    command,
+                    params,
+                    confirmation,
+                );
+                confirmation = confirmation.saturating_add(1);
+                sent
+            },
+            |app_state, header, message| match message {
+                MavMessage::COMMAND_ACK(data)
+                    if is_from_target(app_state, header)
+                        && data.command == command
+                        && data.result != MavResult::MAV_RESULT_IN_PROGRESS =>
+                {
+                    Some(data.result)
+                }
+                _ => None,
+            },
+        )?;
+        println!(
+            "{}",
+            json!({"command": format!("{:?}", command), "result": format!("{:?}", result)})
+        );
+        if result != MavResult::MAV_RESULT_ACCEPTED {
+            return Err(Failure::rejected(format!(
+                "{:?} not accepted: {:?}",
+                command, result
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn is_from_target<M: DialectMessage>(app_state: &AppState<M>, header: &MavHeader) -> bool {
+    app_state.vehicle.target_details.as_ref().is_some_and(|t| {
+        t.target_system_id == header.system_id && t.target_component_id == header.component_id
+    })
+}
+
+/// Parameters of the target by index, without repeated ones
+fn target_parameters<M: DialectMessage>(
+    app_state: &AppState<M>,
+) -> BTreeMap<u16, PARAM_VALUE_DATA> {
+    app_state
+        .vehicle
+        .target_component()
+        .map(|c| {
+            c.parameter_messages
+                .iter()
+                // answers to requests by name may come without an index
+                .filter(|p| p.param_index != u16::MAX)
+                .map(|p| (p.param_index, p.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Integer parameters travel as floats, so values are compared with a tolerance
+fn is_same_value(a: f32, b: f32) -> bool {
+    (a - b).abs() <= f32::EPSILON * a.abs().max(b.abs()).max(1.0)
+}
+
+/// Shortest decimal form of the float, 0.1 instead of 0.10000000149011612
+fn float_json(value: f32) -> Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .map_or(Value::Null, Value::from)
+}
+
+fn parameter_json(parameter: &PARAM_VALUE_DATA) -> Value {
+    json!({
+        "name": decode_param_id(&parameter.param_id),
+        "value": float_json(parameter.param_value),
+        "type": format!("{:?}", parameter.param_type),
+    })
+}
+
+/// MAV_CMD name, with or without the MAV_CMD_ prefix, or number
+fn parse_command(command: &str) -> Result<MavCmd, Failure> {
+    let value = command.parse::<u32>().ok().or_else(|| {
+        let name = command.to_uppercase();
+        let name = match name.starts_with("MAV_CMD_") {
+            true => name,
+            false => format!("MAV_CMD_{}", name),
+        };
+        runtime_dialect::standard()?
+            .enum_value("MAV_CMD", &name)
+            .map(|value| value as u32)
+    });
+    value
+        .and_then(MavCmd::from_u32)
+        .ok_or_else(|| Failure::error(format!("Unknown command {}", command)))
+}
+
+/// NAME,VALUE lines sorted by name, as written by Mission Planner
+fn format_parameter_file(parameters: &[PARAM_VALUE_DATA]) -> String {
+    let mut lines = parameters
+        .iter()
+        .map(|p| format!("{},{}\n", decode_param_id(&p.param_id), p.param_value))
+        .collect::<Vec<_>>();
+    lines.sort();
+    lines.concat()
+}
+
+/// Reads NAME,VALUE or NAME VALUE lines, # starts a comment
+fn parse_parameter_file(text: &str) -> Result<Vec<(String, f32)>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(index, line)| {
+            let mut columns = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|c| !c.is_empty());
+            match (columns.next(), columns.next().map(str::parse)) {
+                (Some(name), Some(Ok(value))) => Ok((name.to_string(), value)),
+                _ => Err(format!("Invalid parameter on line {}", index + 1)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::common::PARAM_VALUE_DATA;
+
+    use crate::headless::format_parameter_file;
+    use crate::headless::is_same_value;
+    use crate::headless::parse_parameter_file;
+    use crate::utils::mavlink::encode_param_id;
+
+    #[test]
+    fn test_parameter_file() {
+        let parameters =
+            [("RATE_RLL_P", 0.135), ("ARMING_CHECK", 1.0)].map(|(name, value)| PARAM_VALUE_DATA {
+                param_id: encode_param_id(name),
+                param_value: value,
+                ..Default::default()
+            });
+        let text = format_parameter_file(&parameters);
+        assert_eq!(text, "ARMING_CHECK,1\nRATE_RLL_P,0.135\n");
+        assert_eq!(
+            parse_parameter_file(&text).unwrap(),
+            vec![
+                ("ARMING_CHECK".to_string(), 1.0),
+                ("RATE_RLL_P".to_string(), 0.135)
+            ]
+        );
+        assert_eq!(
+            parse_parameter_file("# comment\nSYSID_THISMAV 2 # own id\n").unwrap(),
+            vec![("SYSID_THISMAV".to_string(), 2.0)]
+        );
+        assert!(parse_parameter_file("SYSID_THISMAV").is_err());
+        assert!(is_same_value(0.135 * 3.0, 0.405));
+        assert!(!is_same_value(0.135, 0.136));
+    }
+}
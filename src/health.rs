@@ -0,0 +1,97 @@
+use mavlink::common::MavSysStatusSensor;
+use mavlink::common::MavSysStatusSensorExtended;
+use mavlink::common::SYS_STATUS_DATA;
+
+/// Prefixes of STATUSTEXT lines explaining why arming is refused (ArduPilot and PX4)
+const PREARM_PREFIXES: [&str; 2] = ["PreArm:", "Preflight Fail:"];
+
+#[derive(Debug, PartialEq)]
+pub struct SensorHealth {
+    pub name: String,
+    pub present: bool,
+    pub enabled: bool,
+    pub healthy: bool,
+}
+
+impl SensorHealth {
+    /// A sensor is failing when the autopilot uses it but reports it as unhealthy
+    pub fn is_failing(&self) -> bool {
+        self.present && self.enabled && !self.healthy
+    }
+}
+
+pub fn decode_sensor_health(data: &SYS_STATUS_DATA) -> Vec<SensorHealth> {
+    let mut sensors = MavSysStatusSensor::all()
+        .iter_names()
+        .filter(|(_, flag)| *flag != MavSysStatusSensor::MAV_SYS_STATUS_EXTENSION_USED)
+        .map(|(name, flag)| SensorHealth {
+            name: sensor_name(name),
+            present: data.onboard_control_sensors_present.contains(flag),
+            enabled: data.onboard_control_sensors_enabled.contains(flag),
+            healthy: data.onboard_control_sensors_health.contains(flag),
+        })
+        .collect::<Vec<_>>();
+    if data
+        .onboard_control_sensors_present
+        .contains(MavSysStatusSensor::MAV_SYS_STATUS_EXTENSION_USED)
+    {
+        sensors.extend(
+            MavSysStatusSensorExtended::all()
+                .iter_names()
+                .map(|(name, flag)| SensorHealth {
+                    name: sensor_name(name),
+                    present: data.onboard_control_sensors_present_extended.contains(flag),
+                    enabled: data.onboard_control_sensors_enabled_extended.contains(flag),
+                    healthy: data.onboard_control_sensors_health_extended.contains(flag),
+                }),
+        );
+    }
+    sensors
+}
+
+fn sensor_name(flag_name: &str) -> String {
+    flag_name
+        .trim_start_matches("MAV_SYS_STATUS_")
+        .trim_start_matches("SENSOR_")
+        .to_string()
+}
+
+pub fn is_prearm_text(text: &str) -> bool {
+    PREARM_PREFIXES.iter().any(|p| text.starts_with(p))
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::common::MavSysStatusSensor;
+    use mavlink::common::SYS_STATUS_DATA;
+
+    use crate::health::decode_sensor_health;
+    use crate::health::is_prearm_text;
+
+    #[test]
+    fn test_decode_sensor_health() {
+        let gps = MavSysStatusSensor::MAV_SYS_STATUS_SENSOR_GPS;
+        let gyro = MavSysStatusSensor::MAV_SYS_STATUS_SENSOR_3D_GYRO;
+        let data = SYS_STATUS_DATA {
+            onboard_control_sensors_present: gps | gyro,
+            onboard_control_sensors_enabled: gps | gyro,
+            onboard_control_sensors_health: gyro,
+            ..Default::default()
+        };
+        let sensors = decode_sensor_health(&data);
+        let failing = sensors
+            .iter()
+            .filter(|s| s.is_failing())
+            .map(|s| s.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(failing, vec!["GPS"]);
+        let gyro_health = sensors.iter().find(|s| s.name == "3D_GYRO").unwrap();
+        assert!(gyro_health.present && gyro_health.enabled && gyro_health.healthy);
+    }
+
+    #[test]
+    fn test_is_prearm_text() {
+        assert!(is_prearm_text("PreArm: Compass not calibrated"));
+        assert!(!is_prearm_text("EKF3 IMU0 is using GPS"));
+    }
+}
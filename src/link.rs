@@ -0,0 +1,173 @@
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::net::UdpSocket;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+const READ_BUFFER_LEN: usize = 65536;
+
+/// Receiving side of a link, raw bytes are needed to show frames that do not decode
+pub trait LinkReader: Send {
+    /// Next received bytes, empty when the link is closed
+    fn read_chunk(&mut self) -> io::Result<Vec<u8>>;
+    /// Every chunk holds whole frames, a frame cut at the chunk end is truncated
+    fn is_datagram(&self) -> bool;
+}
+
+pub type LinkWriter = Arc<Mutex<Box<dyn Write + Send>>>;
+
+struct StreamReader<R: Read + Send> {
+    inner: R,
+}
+
+impl<R: Read + Send> LinkReader for StreamReader<R> {
+    fn read_chunk(&mut self) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![0u8; READ_BUFFER_LEN];
+        let len = self.inner.read(&mut buffer)?;
+        buffer.truncate(len);
+        Ok(buffer)
+    }
+
+    fn is_datagram(&self) -> bool {
+        false
+    }
+}
+
+struct UdpReader {
+    socket: UdpSocket,
+    /// udpin answers to whoever sent the last datagram
+    peer: Option<Arc<Mutex<Option<SocketAddr>>>>,
+}
+
+impl LinkReader for UdpReader {
+    fn read_chunk(&mut self) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![0u8; READ_BUFFER_LEN];
+        let (len, address) = self.socket.recv_from(&mut buffer)?;
+        if let Some(peer) = &self.peer {
+            *peer.lock().unwrap() = Some(address);
+        }
+        buffer.truncate(len);
+        Ok(buffer)
+    }
+
+    fn is_datagram(&self) -> bool {
+        true
+    }
+}
+
+struct UdpWriter {
+    socket: UdpSocket,
+    peer: Arc<Mutex<Option<SocketAddr>>>,
+}
+
+impl Write for UdpWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self.peer.lock().unwrap() {
+            Some(address) => self.socket.send_to(buf, address),
+            // nobody to talk to until the first datagram arrives
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Opens (tcpout|tcpin|udpout|udpin|udpbcast|serial|file):(ip|dev|path):(port|baud)
+pub fn open(address: &str) -> io::Result<(Box<dyn LinkReader>, LinkWriter)> {
+    let (protocol, address) = address.split_once(':').ok_or(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "Protocol missing",
+    ))?;
+    match protocol {
+        "tcpout" => tcp(TcpStream::connect(address)?),
+        "tcpin" => {
+            // only the first client is served, like the mavlink crate does
+            let (stream, _) = TcpListener::bind(address)?.accept()?;
+            tcp(stream)
+        }
+        "udpin" => {
+            let socket = UdpSocket::bind(address)?;
+            udp(socket, None, true)
+        }
+        "udpout" | "udpbcast" | "udpcast" => {
+            let socket = UdpSocket::bind("0.0.0.0:0")?;
+            socket.set_broadcast(protocol != "udpout")?;
+            udp(socket, Some(resolve(address)?), false)
+        }
+        "serial" => {
+            let (port_name, baud) = address.rsplit_once(':').ok_or(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Baud rate missing",
+            ))?;
+            let baud = baud
+                .parse::<u32>()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Invalid baud rate"))?;
+            let port = serialport::new(port_name, baud)
+                .timeout(READ_TIMEOUT)
+                .open()?;
+            let writer: Box<dyn Write + Send> = Box::new(port.try_clone()?);
+            Ok((
+                Box::new(StreamReader { inner: port }),
+                Arc::new(Mutex::new(writer)),
+            ))
+        }
+        "file" => {
+            let writer: Box<dyn Write + Send> = Box::new(io::sink());
+            Ok((
+                Box::new(StreamReader {
+                    inner: File::open(address)?,
+                }),
+                Arc::new(Mutex::new(writer)),
+            ))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Protocol unsupported",
+        )),
+    }
+}
+
+fn tcp(stream: TcpStream) -> io::Result<(Box<dyn LinkReader>, LinkWriter)> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let writer: Box<dyn Write + Send> = Box::new(stream.try_clone()?);
+    Ok((
+        Box::new(StreamReader { inner: stream }),
+        Arc::new(Mutex::new(writer)),
+    ))
+}
+
+fn udp(
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+    is_server: bool,
+) -> io::Result<(Box<dyn LinkReader>, LinkWriter)> {
+    let peer = Arc::new(Mutex::new(peer));
+    let writer: Box<dyn Write + Send> = Box::new(UdpWriter {
+        socket: socket.try_clone()?,
+        peer: peer.clone(),
+    });
+    Ok((
+        Box::new(UdpReader {
+            socket,
+            peer: is_server.then_some(peer),
+        }),
+        Arc::new(Mutex::new(writer)),
+    ))
+}
+
+fn resolve(address: &str) -> io::Result<SocketAddr> {
+    address.to_socket_addrs()?.next().ok_or(io::Error::new(
+        io::ErrorKind::AddrNotAvailable,
+        "Unknown host",
+    ))
+}
@@ -0,0 +1,210 @@
+use std::collections::VecDeque;
+
+use chrono::DateTime;
+use chrono::Local;
+use mavlink::common::RADIO_STATUS_DATA;
+
+/// Rates are averaged over this many last complete seconds
+const RATE_WINDOW_SECONDS: i64 = 5;
+
+/// Seconds kept for sparklines
+pub const HISTORY_SECONDS: i64 = 120;
+
+/// Counters derived from the sequence numbers of one sender
+#[derive(Default)]
+pub struct SequenceStats {
+    last_sequence: Option<u8>,
+    pub received: u64,
+    pub lost: u64,
+    pub duplicates: u64,
+    pub out_of_order: u64,
+}
+
+impl SequenceStats {
+    pub fn record(&mut self, sequence: u8) {
+        self.received += 1;
+        let Some(last_sequence) = self.last_sequence else {
+            self.last_sequence = Some(sequence);
+            return;
+        };
+        match sequence.wrapping_sub(last_sequence) {
+            0 => self.duplicates += 1,
+            // a jump forward, everything in between is lost
+            gap @ 1..=127 => {
+                self.lost += gap as u64 - 1;
+                self.last_sequence = Some(sequence);
+            }
+            // a frame from behind arrived late, it was counted as lost
+            _ => {
+                self.out_of_order += 1;
+                self.lost = self.lost.saturating_sub(1);
+            }
+        }
+    }
+
+    pub fn loss_percent(&self) -> f32 {
+        let expected = self.received + self.lost;
+        if expected == 0 {
+            0.0
+        } else {
+            self.lost as f32 * 100.0 / expected as f32
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct TrafficBucket {
+    messages: u64,
+    bytes: u64,
+    lost: u64,
+}
+
+/// Sequence counters and per second traffic of a component or of the whole link
+#[derive(Default)]
+pub struct LinkStats {
+    pub sequence: SequenceStats,
+    buckets: VecDeque<(i64, TrafficBucket)>,
+}
+
+impl LinkStats {
+    pub fn record(&mut self, sequence: Option<u8>, bytes: usize, now: DateTime<Local>) {
+        let lost_before = self.sequence.lost;
+        if let Some(sequence) = sequence {
+            self.sequence.record(sequence);
+        }
+        let bucket = current_bucket(&mut self.buckets, now);
+        bucket.messages += 1;
+        bucket.bytes += bytes as u64;
+        bucket.lost += self.sequence.lost.saturating_sub(lost_before);
+    }
+
+    pub fn messages_per_second(&self, now: DateTime<Local>) -> f32 {
+        self.rate(now, |b| b.messages)
+    }
+
+    pub fn bytes_per_second(&self, now: DateTime<Local>) -> f32 {
+        self.rate(now, |b| b.bytes)
+    }
+
+    fn rate(&self, now: DateTime<Local>, value: fn(&TrafficBucket) -> u64) -> f32 {
+        let second = now.timestamp();
+        let total = self
+            .buckets
+            .iter()
+            .filter(|(s, _)| *s < second && *s >= second - RATE_WINDOW_SECONDS)
+            .map(|(_, b)| value(b))
+            .sum::<u64>();
+        total as f32 / RATE_WINDOW_SECONDS as f32
+    }
+
+    pub fn messages_history(&self, now: DateTime<Local>) -> Vec<u64> {
+        history(&self.buckets, now, |b| b.messages)
+    }
+
+    pub fn bytes_history(&self, now: DateTime<Local>) -> Vec<u64> {
+        history(&self.buckets, now, |b| b.bytes)
+    }
+
+    pub fn lost_history(&self, now: DateTime<Local>) -> Vec<u64> {
+        history(&self.buckets, now, |b| b.lost)
+    }
+}
+
+/// Signal levels reported by telemetry radios
+#[derive(Default)]
+pub struct RadioStats {
+    pub last_radio_status: Option<RADIO_STATUS_DATA>,
+    pub last_received: Option<DateTime<Local>>,
+    buckets: VecDeque<(i64, RADIO_STATUS_DATA)>,
+}
+
+impl RadioStats {
+    pub fn record(&mut self, data: &RADIO_STATUS_DATA, now: DateTime<Local>) {
+        *current_bucket(&mut self.buckets, now) = data.clone();
+        self.last_radio_status = Some(data.clone());
+        self.last_received = Some(now);
+    }
+
+    pub fn rssi_history(&self, now: DateTime<Local>) -> Vec<u64> {
+        history(&self.buckets, now, |d| d.rssi as u64)
+    }
+
+    pub fn remote_rssi_history(&self, now: DateTime<Local>) -> Vec<u64> {
+        history(&self.buckets, now, |d| d.remrssi as u64)
+    }
+}
+
+fn current_bucket<T: Default>(buckets: &mut VecDeque<(i64, T)>, now: DateTime<Local>) -> &mut T {
+    let second = now.timestamp();
+    if buckets.back().is_none_or(|(s, _)| *s != second) {
+        buckets.push_back((second, T::default()));
+    }
+    while buckets
+        .front()
+        .is_some_and(|(s, _)| *s <= second - HISTORY_SECONDS)
+    {
+        buckets.pop_front();
+    }
+    &mut buckets.back_mut().unwrap().1
+}
+
+/// One value per second, oldest first, seconds without data are zero
+fn history<T>(
+    buckets: &VecDeque<(i64, T)>,
+    now: DateTime<Local>,
+    value: fn(&T) -> u64,
+) -> Vec<u64> {
+    let first_second = now.timestamp() - HISTORY_SECONDS + 1;
+    let mut values = vec![0; HISTORY_SECONDS as usize];
+    for (second, bucket) in buckets {
+        if let Some(index) = second
+            .checked_sub(first_second)
+            .and_then(|i| usize::try_from(i).ok())
+            .filter(|i| *i < values.len())
+        {
+            values[index] = value(bucket);
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use chrono::TimeDelta;
+    use chrono::TimeZone;
+
+    use crate::link_stats::HISTORY_SECONDS;
+    use crate::link_stats::LinkStats;
+    use crate::link_stats::SequenceStats;
+
+    #[test]
+    fn test_sequence_stats() {
+        let mut stats = SequenceStats::default();
+        for sequence in [250, 251, 251, 254, 253, 255, 0, 3] {
+            stats.record(sequence);
+        }
+        assert_eq!(stats.received, 8);
+        assert_eq!(stats.duplicates, 1);
+        assert_eq!(stats.out_of_order, 1);
+        // 252, 1 and 2 never arrived
+        assert_eq!(stats.lost, 3);
+        assert_eq!(stats.loss_percent(), 3.0 * 100.0 / 11.0);
+    }
+
+    #[test]
+    fn test_rates_and_history() {
+        let mut stats = LinkStats::default();
+        let start = Local.timestamp_opt(1_700_000_000, 0).unwrap();
+        for i in 0..100 {
+            stats.record(Some(i as u8), 20, start + TimeDelta::milliseconds(100 * i));
+        }
+        let now = start + TimeDelta::seconds(10);
+        assert_eq!(stats.messages_per_second(now), 10.0);
+        assert_eq!(stats.bytes_per_second(now), 200.0);
+        let history = stats.messages_history(now);
+        assert_eq!(history.len(), HISTORY_SECONDS as usize);
+        assert_eq!(history.iter().sum::<u64>(), 100);
+        assert_eq!(*history.last().unwrap(), 0);
+    }
+}
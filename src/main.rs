@@ -1,23 +1,52 @@
+mod alerts;
 mod cli;
+mod dataflash;
+mod dialect;
+mod export;
+mod frame;
+mod headless;
+mod health;
+mod link;
+mod link_stats;
 mod mavlink_client;
+mod message_fields;
+mod onboard_log;
+mod plot;
+mod replay;
+mod runtime_dialect;
+mod signing;
+mod status_text;
+mod streams;
+mod tlog;
+mod ulog;
 mod utils;
+mod watch;
+mod waypoints;
 use chrono::DateTime;
 use chrono::Local;
+use chrono::TimeDelta;
 use clap::Parser;
 use crossterm::event::KeyEvent;
-use mavlink::MavConnection;
-use mavlink::MavFrame;
+use mavlink::MavHeader;
 use ratatui::DefaultTerminal;
 use ratatui::widgets::TableState;
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::sync::atomic::AtomicU8;
 use std::sync::mpsc;
 use std::thread;
+use std::time::Duration;
 use std::time::SystemTime;
 use strum::Display;
 use strum::EnumIter;
 use strum::IntoEnumIterator;
 use utils::mavlink::decode_param_id;
+use utils::mavlink::is_autopilot_heartbeat;
 
 use crossterm::event::Event;
 use crossterm::event::KeyCode;
@@ -25,15 +54,53 @@ use crossterm::event::KeyModifiers;
 use crossterm::event::{self};
 mod render;
 
+use crate::alerts::Alerts;
 use crate::cli::Args;
+use crate::cli::Dialect;
+use crate::dialect::DialectMessage;
+use crate::dialect::ExtendedMessage;
+use crate::export::MessageFilter;
+use crate::frame::FrameContent;
+use crate::frame::ReceivedFrame;
+use crate::frame::UndecodableReason;
+use crate::link::LinkWriter;
+use crate::link_stats::LinkStats;
+use crate::link_stats::RadioStats;
+use crate::mavlink_client::request_data_streams;
+use crate::mavlink_client::request_message;
 use crate::mavlink_client::request_mission_count;
 use crate::mavlink_client::request_parameters;
+use crate::mavlink_client::set_message_interval;
 use crate::mavlink_client::synchronise_mission_items;
+use crate::message_fields::MessageField;
+use crate::plot::Plot;
+use crate::replay::Replay;
+use crate::runtime_dialect::RuntimeDialect;
+use crate::signing::SignatureStatus;
+use crate::signing::Signing;
+use crate::status_text::StatusTextLog;
+use crate::streams::StreamRates;
+use crate::tlog::TlogWriter;
+use crate::watch::WatchList;
 use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use mavlink::common::COMMAND_ACK_DATA;
+use mavlink::common::HEARTBEAT_DATA;
 use mavlink::common::MISSION_ITEM_INT_DATA;
-use mavlink::common::MavMessage;
+use mavlink::common::MavComponent;
 use mavlink::common::MavModeFlag;
 use mavlink::common::PARAM_VALUE_DATA;
+use mavlink::common::SYS_STATUS_DATA;
+use mavlink::error::MessageWriteError;
+
+/// Rate of legacy data streams requested with (a) when no --stream-rate is given
+const DEFAULT_STREAM_RATE_HZ: u16 = 4;
+/// Replay jump of the (←/→) keys
+const REPLAY_SEEK_SECONDS: i64 = 10;
+/// Interval of timing alert checks and redraws without new frames
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a message heard on one link counts as a duplicate when it arrives on another
+const DUPLICATE_WINDOW: TimeDelta = TimeDelta::seconds(1);
 
 #[derive(Default)]
 struct MissionDetails {
@@ -41,29 +108,199 @@ struct MissionDetails {
     last_mission_request: Option<DateTime<Local>>,
     mission_items_to_load_num: Option<u16>,
 }
-#[derive(Default, Clone)]
+#[derive(Default, Clone, PartialEq)]
 struct TargetDetails {
     target_system_id: u8,
     target_component_id: u8,
 }
 
+/// Identity of the debugger itself on the MAVLink network
+#[derive(Clone)]
+struct SourceDetails {
+    source_system_id: u8,
+    source_component_id: u8,
+}
+
+impl Default for SourceDetails {
+    fn default() -> Self {
+        SourceDetails {
+            source_system_id: 255,
+            source_component_id: MavComponent::MAV_COMP_ID_MISSIONPLANNER as u8,
+        }
+    }
+}
+
+struct RecentMessage {
+    link_id: usize,
+    sequence: u8,
+    message_id: u32,
+    payload: Vec<u8>,
+    time: DateTime<Local>,
+}
+
+/// Everything received from a single (system id, component id) pair
 #[derive(Default)]
-struct Vehicle {
-    messages: Vec<MavMessage>,
+struct ComponentDetails {
+    heartbeat: Option<HEARTBEAT_DATA>,
+    last_heartbeat: Option<DateTime<Local>>,
+    /// Time of the last message of any type
+    last_seen: Option<DateTime<Local>>,
+    messages_num: usize,
     parameter_messages: Vec<PARAM_VALUE_DATA>,
-    is_armed: bool,
     last_parameters_request: Option<DateTime<Local>>,
-    connection: Option<Arc<Box<dyn MavConnection<MavMessage> + Send + Sync>>>,
-    target_details: Option<TargetDetails>,
     mission_details: Mutex<MissionDetails>,
+    sys_status: Option<SYS_STATUS_DATA>,
+    last_sys_status: Option<DateTime<Local>>,
+    stream_rates: StreamRates,
+    data_streams_requested: bool,
+    last_command_ack: Option<COMMAND_ACK_DATA>,
+    /// traffic of the component on every link it was heard on
+    link_stats: BTreeMap<usize, LinkStats>,
+    /// Messages of the last DUPLICATE_WINDOW
+    recent_messages: VecDeque<RecentMessage>,
+}
+
+impl ComponentDetails {
+    fn is_armed(&self) -> bool {
+        self.heartbeat.as_ref().is_some_and(|h| {
+            h.base_mode
+                .contains(MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED)
+        })
+    }
+    /// Silent for longer than the timeout, judged by heartbeats when the component sends them
+    fn is_lost(&self, now: DateTime<Local>, timeout: TimeDelta) -> bool {
+        self.last_heartbeat
+            .or(self.last_seen)
+            .is_some_and(|t| now - t > timeout)
+    }
+    /// Same message already heard on another link, remembers it otherwise; the payload is
+    /// compared too, fast senders wrap their sequence numbers within the window
+    fn is_duplicate(&mut self, message: RecentMessage) -> bool {
+        self.recent_messages
+            .retain(|m| message.time - m.time < DUPLICATE_WINDOW);
+        let is_duplicate = self.recent_messages.iter().any(|m| {
+            m.link_id != message.link_id
+                && m.sequence == message.sequence
+                && m.message_id == message.message_id
+                && m.payload == message.payload
+        });
+        if !is_duplicate {
+            self.recent_messages.push_back(message);
+        }
+        is_duplicate
+    }
+}
+
+struct Vehicle<M: DialectMessage> {
+    messages: Vec<ReceivedFrame<M>>,
+    connection: Option<LinkWriter>,
+    source_details: SourceDetails,
+    /// sequence number of the next sent message, shared with the heartbeat thread
+    sequence: Arc<AtomicU8>,
+    target_details: Option<TargetDetails>,
+    /// target was chosen by the user and is not changed automatically
+    is_target_locked: bool,
+    components: BTreeMap<(u8, u8), ComponentDetails>,
+    status_text_log: StatusTextLog,
+    undecodable_frames: BTreeMap<UndecodableReason, usize>,
+    /// traffic of every frame, decodable or not
+    link_stats: LinkStats,
+    radio_stats: RadioStats,
+    /// key checking received signatures and signing sent messages
+    signing: Option<Arc<Signing>>,
+    signature_stats: BTreeMap<SignatureStatus, usize>,
+}
+
+impl<M: DialectMessage> Default for Vehicle<M> {
+    fn default() -> Self {
+        Vehicle {
+            messages: vec![],
+            connection: None,
+            source_details: SourceDetails::default(),
+            sequence: Arc::default(),
+            target_details: None,
+            is_target_locked: false,
+            components: BTreeMap::new(),
+            status_text_log: StatusTextLog::default(),
+            undecodable_frames: BTreeMap::new(),
+            link_stats: LinkStats::default(),
+            radio_stats: RadioStats::default(),
+            signing: None,
+            signature_stats: BTreeMap::new(),
+        }
+    }
 }
 
-enum AppEvent {
+impl<M: DialectMessage> Vehicle<M> {
+    fn target_component(&self) -> Option<&ComponentDetails> {
+        self.target_details.as_ref().and_then(|t| {
+            self.components
+                .get(&(t.target_system_id, t.target_component_id))
+        })
+    }
+    /// Traffic of every sender on every link it was heard on, ordered by sender and link
+    fn sender_links(&self) -> impl Iterator<Item = ((u8, u8), usize, &LinkStats)> {
+        self.components.iter().flat_map(|(key, c)| {
+            c.link_stats
+                .iter()
+                .map(|(link_id, stats)| (*key, *link_id, stats))
+        })
+    }
+    fn target_component_mut(&mut self) -> Option<&mut ComponentDetails> {
+        self.target_details.as_ref().and_then(|t| {
+            self.components
+                .get_mut(&(t.target_system_id, t.target_component_id))
+        })
+    }
+    /// Picks an autopilot among components sending heartbeats, preferring the main autopilot
+    /// component and then the lowest system id; a lost target is kept when there is no other
+    fn auto_select_target(
+        &mut self,
+        system_id: Option<u8>,
+        component_id: Option<u8>,
+        now: DateTime<Local>,
+        heartbeat_timeout: TimeDelta,
+    ) {
+        if self.is_target_locked {
+            return;
+        }
+        let autopilot_component_id = MavComponent::MAV_COMP_ID_AUTOPILOT1 as u8;
+        let best_candidate = self
+            .components
+            .iter()
+            .filter(|(_, c)| c.heartbeat.as_ref().is_some_and(is_autopilot_heartbeat))
+            .filter(|(_, c)| !c.is_lost(now, heartbeat_timeout))
+            .map(|(key, _)| *key)
+            .filter(|(s, _)| system_id.is_none_or(|id| id == *s))
+            .filter(|(_, c)| component_id.is_none_or(|id| id == *c))
+            .min_by_key(|(s, c)| (*c != autopilot_component_id, *s, *c));
+        if let Some((target_system_id, target_component_id)) = best_candidate {
+            self.target_details = Some(TargetDetails {
+                target_system_id,
+                target_component_id,
+            });
+        }
+    }
+}
+
+enum AppEvent<M: DialectMessage> {
     Input(crossterm::event::Event),
-    Mavlink(Box<MavFrame<MavMessage>>),
+    Mavlink(Box<ReceivedFrame<M>>),
+    /// Replay went back in time, frames are sent again from the first one
+    ReplayRestart,
+    /// Sent every second to evaluate timing alert rules
+    Tick,
+}
+
+fn handle_ticks<M: DialectMessage>(tx: mpsc::Sender<AppEvent<M>>) {
+    thread::spawn(move || {
+        while tx.send(AppEvent::Tick).is_ok() {
+            thread::sleep(TICK_INTERVAL);
+        }
+    });
 }
 
-fn handle_input(tx: mpsc::Sender<AppEvent>) {
+fn handle_input<M: DialectMessage>(tx: mpsc::Sender<AppEvent<M>>) {
     thread::spawn(move || {
         loop {
             if let Ok(key_event) = event::read() {
@@ -78,83 +315,490 @@ enum Screen {
     #[default]
     Status,
     Messages,
+    Plot,
+    Watch,
+    Console,
+    Alerts,
+    Health,
+    Link,
+    Streams,
     Parameters,
     Mission,
 }
-pub struct AppState {
+pub struct AppState<M: DialectMessage> {
     args: crate::cli::Args,
 
-    vehicle: Vehicle,
+    vehicle: Vehicle<M>,
 
+    components_table_state: TableState,
     messages_table_state: TableState,
+    console_table_state: TableState,
+    health_table_state: TableState,
+    link_table_state: TableState,
+    streams_table_state: TableState,
     parameters_table_state: TableState,
     mission_table_state: TableState,
+    plot_table_state: TableState,
+    watch_table_state: TableState,
+
+    plot: Plot,
+    /// Index of the numeric field of the selected message that (p) and (w) pin
+    plot_field_index: usize,
+    watch: WatchList,
+    watch_notice: Option<String>,
+
+    alerts: Alerts,
+    alerts_table_state: TableState,
+
+    /// Link shown on the Messages screen, every link when None
+    link_filter: Option<usize>,
+
+    console_notice: Option<String>,
+    export_notice: Option<String>,
+
+    recorder: Option<TlogWriter>,
+    recording_notice: Option<String>,
+    /// Why the last message could not be sent, cleared by the next one sent
+    send_error: Option<String>,
+
+    replay: Option<Replay>,
 
     is_exit: bool,
     screen: Screen,
 }
-impl AppState {
-    fn default(args: crate::cli::Args, vehicle: Vehicle) -> Self {
+impl<M: DialectMessage> AppState<M> {
+    fn default(args: crate::cli::Args, mut vehicle: Vehicle<M>) -> Self {
+        if let (Some(target_system_id), Some(target_component_id)) =
+            (args.target_system, args.target_component)
+        {
+            vehicle.target_details = Some(TargetDetails {
+                target_system_id,
+                target_component_id,
+            });
+            vehicle.is_target_locked = true;
+        }
         AppState {
             args,
             vehicle,
             is_exit: false,
+            components_table_state: TableState::default().with_selected(Some(0)),
             messages_table_state: TableState::default().with_selected(Some(0)),
+            console_table_state: TableState::default().with_selected(Some(0)),
+            health_table_state: TableState::default().with_selected(Some(0)),
+            link_table_state: TableState::default().with_selected(Some(0)),
+            streams_table_state: TableState::default().with_selected(Some(0)),
             parameters_table_state: TableState::default().with_selected(Some(0)),
             mission_table_state: TableState::default().with_selected(Some(0)),
+            plot_table_state: TableState::default().with_selected(Some(0)),
+            plot: Plot::default(),
+            plot_field_index: 0,
+            watch_table_state: TableState::default().with_selected(Some(0)),
+            watch: WatchList::default(),
+            watch_notice: None,
+            alerts: Alerts::default(),
+            alerts_table_state: TableState::default().with_selected(Some(0)),
+            link_filter: None,
+            console_notice: None,
+            export_notice: None,
+            recorder: None,
+            recording_notice: None,
+            send_error: None,
+            replay: None,
             screen: Screen::Status,
         }
     }
-    fn get_selected_message(&self) -> Option<MavMessage> {
+    fn heartbeat_timeout(&self) -> TimeDelta {
+        TimeDelta::seconds(self.args.heartbeat_timeout as i64)
+    }
+    fn report_send(&mut self, result: Result<(), MessageWriteError>) {
+        self.send_error = result.err().map(|e| e.to_string());
+    }
+    /// Target that stopped sending heartbeats, with the time since the last one
+    fn lost_target(&self) -> Option<TimeDelta> {
+        let now = self.now();
+        let component = self.vehicle.target_component()?;
+        if !component.is_lost(now, self.heartbeat_timeout()) {
+            return None;
+        }
+        component
+            .last_heartbeat
+            .or(component.last_seen)
+            .map(|t| now - t)
+    }
+    /// Time of the link, the time of the last replayed frame when replaying
+    fn now(&self) -> DateTime<Local> {
+        self.replay
+            .as_ref()
+            .map_or_else(Local::now, |r| r.current_time())
+    }
+    fn restart_replay(&mut self) {
+        let vehicle = std::mem::take(&mut self.vehicle);
+        self.vehicle = Vehicle {
+            connection: vehicle.connection,
+            source_details: vehicle.source_details,
+            sequence: vehicle.sequence,
+            target_details: vehicle.target_details.filter(|_| vehicle.is_target_locked),
+            is_target_locked: vehicle.is_target_locked,
+            signing: vehicle.signing,
+            ..Default::default()
+        };
+        for table_state in [
+            &mut self.components_table_state,
+            &mut self.messages_table_state,
+            &mut self.console_table_state,
+            &mut self.health_table_state,
+            &mut self.link_table_state,
+            &mut self.streams_table_state,
+            &mut self.parameters_table_state,
+            &mut self.mission_table_state,
+        ] {
+            table_state.select_first();
+        }
+        self.plot.clear_points();
+        self.watch.clear_values();
+        self.alerts.reset();
+        self.alerts_table_state.select_first();
+    }
+    /// Messages of the link chosen with (l), with their index among messages of every link
+    fn shown_messages(&self) -> impl Iterator<Item = (usize, &ReceivedFrame<M>)> {
+        let link_filter = self.link_filter;
+        self.vehicle
+            .messages
+            .iter()
+            .enumerate()
+            .filter(move |(_, f)| link_filter.is_none_or(|id| id == f.link_id))
+    }
+    /// Shows messages of the next link, after the last one messages of every link
+    fn change_link_filter(&mut self) {
+        let links_num = self.args.addresses.len();
+        if links_num < 2 {
+            return;
+        }
+        self.link_filter = match self.link_filter {
+            None => Some(0),
+            Some(id) if id + 1 < links_num => Some(id + 1),
+            Some(_) => None,
+        };
+        self.messages_table_state.select_first();
+    }
+    fn get_selected_message(&self) -> Option<ReceivedFrame<M>> {
         let selected_message_num = self.messages_table_state.selected();
         if let Some(index) = selected_message_num {
-            self.vehicle.messages.get(index).cloned()
+            self.shown_messages().nth(index).map(|(_, f)| f.clone())
         } else {
             None
         }
     }
+    /// Numeric field of the selected message chosen with (n/N), with the message header and name
+    fn get_selected_plot_field(&self) -> Option<(MavHeader, String, MessageField)> {
+        let FrameContent::Message { header, message } = self.get_selected_message()?.content else {
+            return None;
+        };
+        let fields = message
+            .fields()
+            .into_iter()
+            .filter(|f| f.numeric_value().is_some())
+            .collect::<Vec<_>>();
+        if fields.is_empty() {
+            return None;
+        }
+        let field = fields[self.plot_field_index % fields.len()].clone();
+        Some((header, message.message_name().to_string(), field))
+    }
+    fn change_plot_field(&mut self, is_next: bool) {
+        let Some(FrameContent::Message { message, .. }) =
+            self.get_selected_message().map(|f| f.content)
+        else {
+            return;
+        };
+        let count = message
+            .fields()
+            .iter()
+            .filter(|f| f.numeric_value().is_some())
+            .count();
+        if count == 0 {
+            return;
+        }
+        let index = self.plot_field_index % count;
+        self.plot_field_index = if is_next {
+            (index + 1) % count
+        } else {
+            (index + count - 1) % count
+        };
+    }
+    fn pin_selected_field(&mut self) {
+        if let Some((header, message_name, field)) = self.get_selected_plot_field() {
+            self.plot.toggle_pin(&header, &message_name, &field);
+        }
+    }
+    fn watch_selected_field(&mut self) {
+        if let Some((header, message_name, field)) = self.get_selected_plot_field() {
+            self.watch.toggle(&header, &message_name, &field);
+        }
+    }
+    fn remove_selected_watch(&mut self) {
+        if let Some(index) = self.watch_table_state.selected()
+            && index < self.watch.fields.len()
+        {
+            self.watch.fields.remove(index);
+        }
+    }
+    /// Writes the session statistics of watched fields, returns the notice to show
+    fn save_watch_report(&self) -> String {
+        let path = format!("watch_{}.txt", Local::now().format("%Y%m%d_%H%M%S"));
+        match self
+            .watch
+            .save_report(std::path::Path::new(&path), self.now())
+        {
+            Ok(()) => format!("Saved to {}", path),
+            Err(e) => format!("Could not save: {}", e),
+        }
+    }
+    fn ring_bell(&self, triggered_alerts: usize) {
+        if triggered_alerts > 0 && self.args.alert_bell {
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+        }
+    }
+    fn remove_selected_series(&mut self) {
+        if let Some(index) = self.plot_table_state.selected()
+            && index < self.plot.series.len()
+        {
+            self.plot.series.remove(index);
+        }
+    }
     fn get_selected_parameter(&self) -> Option<PARAM_VALUE_DATA> {
         let selected_parameter_num = self.parameters_table_state.selected();
         if let Some(index) = selected_parameter_num {
-            self.vehicle.parameter_messages.get(index).cloned()
+            self.vehicle
+                .target_component()
+                .and_then(|c| c.parameter_messages.get(index).cloned())
         } else {
             None
         }
     }
+    fn get_selected_stream_message_id(&self) -> Option<u32> {
+        let selected_stream_num = self.streams_table_state.selected()?;
+        self.vehicle.target_component().and_then(|c| {
+            c.stream_rates
+                .rows()
+                .get(selected_stream_num)
+                .map(|r| r.message_id)
+        })
+    }
+    fn change_selected_stream_rate(&mut self, is_increase: bool) {
+        let Some(message_id) = self.get_selected_stream_message_id() else {
+            return;
+        };
+        let desired_hz = self
+            .vehicle
+            .target_component_mut()
+            .and_then(|c| c.stream_rates.change_desired_rate(message_id, is_increase));
+        if let Some(hz) = desired_hz {
+            let result = set_message_interval(&self.vehicle, message_id, streams::interval_us(hz));
+            self.report_send(result);
+        }
+    }
+    fn select_target(&mut self) {
+        let selected_component = self
+            .components_table_state
+            .selected()
+            .and_then(|index| self.vehicle.components.keys().nth(index));
+        if let Some((system_id, component_id)) = selected_component {
+            self.vehicle.target_details = Some(TargetDetails {
+                target_system_id: *system_id,
+                target_component_id: *component_id,
+            });
+            self.vehicle.is_target_locked = true;
+            self.parameters_table_state.select_first();
+            self.mission_table_state.select_first();
+            self.health_table_state.select_first();
+            self.streams_table_state.select_first();
+        }
+    }
     fn clear_parameters(&mut self) {
-        self.vehicle.parameter_messages.clear();
-        self.vehicle.last_parameters_request = None;
+        if let Some(component) = self.vehicle.target_component_mut() {
+            component.parameter_messages.clear();
+            component.last_parameters_request = None;
+        }
         self.parameters_table_state.select_first();
     }
+    fn save_console(&mut self) {
+        let path = format!("statustext_{}.txt", Local::now().format("%Y%m%d_%H%M%S"));
+        self.console_notice = Some(
+            match self
+                .vehicle
+                .status_text_log
+                .save(std::path::Path::new(&path))
+            {
+                Ok(()) => format!("Saved to {}", path),
+                Err(e) => format!("Could not save: {}", e),
+            },
+        );
+    }
+    /// Writes the message history as JSON Lines and a CSV directory next to each other
+    fn export_messages(&mut self, is_selected_type_only: bool) {
+        let mut filter = MessageFilter::default();
+        if is_selected_type_only {
+            let Some(FrameContent::Message { message, .. }) =
+                self.get_selected_message().map(|f| f.content)
+            else {
+                self.export_notice = Some("Select a message to export its type".to_string());
+                return;
+            };
+            filter
+                .message_names
+                .push(message.message_name().to_string());
+        }
+        let name = format!("messages_{}", Local::now().format("%Y%m%d_%H%M%S"));
+        let jsonl_path = PathBuf::from(format!("{}.jsonl", name));
+        let csv_dir = PathBuf::from(&name);
+        let messages = &self.vehicle.messages;
+        self.export_notice = Some(
+            match export::write_jsonl(messages, &filter, &jsonl_path)
+                .and_then(|_| export::write_csv(messages, &filter, &csv_dir))
+            {
+                Ok(written) => format!(
+                    "Exported {} messages to {} and {}/",
+                    written,
+                    jsonl_path.display(),
+                    csv_dir.display()
+                ),
+                Err(e) => format!("Could not export: {}", e),
+            },
+        );
+    }
+    fn toggle_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            self.recording_notice = Some(format!(
+                "Recorded {} frames to {}",
+                recorder.frames_written,
+                recorder.path.display()
+            ));
+            return;
+        }
+        let path = self.args.record.clone().unwrap_or_else(|| {
+            PathBuf::from(format!("{}.tlog", Local::now().format("%Y%m%d_%H%M%S")))
+        });
+        match TlogWriter::open(&path) {
+            Ok(recorder) => {
+                self.recorder = Some(recorder);
+                self.recording_notice = None;
+            }
+            Err(e) => {
+                self.recording_notice = Some(format!("Could not record: {}", e));
+            }
+        }
+    }
+    fn record_frame(&mut self, frame: &ReceivedFrame<M>, now: DateTime<Local>) {
+        let Some(recorder) = &mut self.recorder else {
+            return;
+        };
+        // an entry is as long as its frame header says, a cut frame would swallow the next one
+        if matches!(
+            frame.content,
+            FrameContent::Undecodable(UndecodableReason::Truncated)
+        ) {
+            return;
+        }
+        if let Err(e) = recorder.write(now, &frame.raw) {
+            self.recorder = None;
+            self.recording_notice = Some(format!("Recording stopped: {}", e));
+        }
+    }
     fn clear_mission(&mut self) {
-        let mut details = self.vehicle.mission_details.lock().unwrap();
-        details.mission_messages.clear();
-        details.last_mission_request = None;
-        details.mission_items_to_load_num = None;
+        if let Some(component) = self.vehicle.target_component() {
+            let mut details = component.mission_details.lock().unwrap();
+            details.mission_messages.clear();
+            details.last_mission_request = None;
+            details.mission_items_to_load_num = None;
+        }
         self.mission_table_state.select_first();
     }
 }
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     let args = Args::parse();
 
     color_eyre::install()?;
-    let (event_tx, event_rx) = mpsc::channel::<AppEvent>();
+    runtime_dialect::install_standard(RuntimeDialect::standard(args.dialect.definitions_file())?);
+    if let Some(path) = &args.dialect_xml {
+        runtime_dialect::install(RuntimeDialect::load(path)?);
+    }
+    match args.dialect {
+        Dialect::Common => run_dialect::<ExtendedMessage<mavlink::common::MavMessage>>(args),
+        Dialect::Ardupilotmega => {
+            run_dialect::<ExtendedMessage<mavlink::ardupilotmega::MavMessage>>(args)
+        }
+        Dialect::Development => {
+            run_dialect::<ExtendedMessage<mavlink::development::MavMessage>>(args)
+        }
+        Dialect::All => run_dialect::<ExtendedMessage<mavlink::all::MavMessage>>(args),
+    }
+}
+
+fn run_dialect<M: DialectMessage>(mut args: Args) -> Result<ExitCode> {
+    if let Some(command) = args.command.take() {
+        return Ok(headless::run::<M>(args, command));
+    }
+    let alerts = Alerts::load(&args.alert, args.alerts_file.as_deref())?;
+    let signing = Signing::load(
+        args.signing_key.as_deref(),
+        args.signing_passphrase.as_deref(),
+        args.signing_link_id,
+    )?;
+    if args.addresses.len() > 1
+        && args
+            .addresses
+            .iter()
+            .any(|a| mavlink_client::is_log_address(a))
+    {
+        return Err(eyre!("Logs are replayed alone, without other addresses"));
+    }
+    let (event_tx, event_rx) = mpsc::channel::<AppEvent<M>>();
     handle_input(event_tx.clone());
-    let mut terminal = ratatui::init();
+    handle_ticks(event_tx.clone());
 
-    let vehicle = mavlink_client::connect(args.address.as_str(), event_tx.clone());
+    let source_details = args.source_details();
+    let (vehicle, replay) = match mavlink_client::read_log(&args.addresses[0]) {
+        Some(frames) => {
+            let (vehicle, replay) =
+                mavlink_client::replay(frames?, source_details, event_tx.clone());
+            (vehicle, Some(replay))
+        }
+        None => (
+            mavlink_client::connect(
+                &args.addresses,
+                args.forward_filter(),
+                signing.map(Arc::new),
+                source_details,
+                event_tx.clone(),
+            )?,
+            None,
+        ),
+    };
+    // errors of opening the addresses are printed to the normal screen
+    let mut terminal = ratatui::init();
+    let is_recording = args.record.is_some();
     let mut app_state = AppState::default(args, vehicle);
+    app_state.replay = replay;
+    app_state.alerts = alerts;
+    if is_recording {
+        app_state.toggle_recording();
+    }
 
     let app_result = run(&mut app_state, &mut terminal, event_rx);
     ratatui::restore();
-    app_result
+    if !app_state.watch.fields.is_empty() {
+        println!("Watch summary: {}", app_state.save_watch_report());
+    }
+    app_result.map(|_| ExitCode::SUCCESS)
 }
 
-fn run(
-    app_state: &mut AppState,
+fn run<M: DialectMessage>(
+    app_state: &mut AppState<M>,
     terminal: &mut DefaultTerminal,
-    rx: mpsc::Receiver<AppEvent>,
+    rx: mpsc::Receiver<AppEvent<M>>,
 ) -> Result<()> {
     let mut fps_limiter = utils::tui::FPSLimiter::default(5);
     while !app_state.is_exit {
@@ -163,107 +807,274 @@ fn run(
             AppEvent::Input(event) => {
                 handle_input_event(app_state, event);
                 match app_state.screen {
-                    Screen::Status => {
-                        terminal.draw(|frame| render::draw_status_screen(app_state, frame))?;
-                    }
-                    Screen::Messages => {
-                        terminal.draw(|frame| render::draw_messages_screen(app_state, frame))?;
-                    }
-                    Screen::Parameters => {
-                        if app_state.vehicle.last_parameters_request.is_none() {
-                            request_parameters(&mut app_state.vehicle);
-                            app_state.vehicle.last_parameters_request = Some(Local::now());
-                        }
-                        terminal.draw(|frame| render::draw_parameters_screen(app_state, frame))?;
-                    }
-                    Screen::Mission => {
+                    Screen::Parameters
                         if app_state
                             .vehicle
-                            .mission_details
-                            .lock()
-                            .unwrap()
-                            .last_mission_request
-                            .is_none()
-                        {
-                            request_mission_count(&mut app_state.vehicle);
-                            app_state
-                                .vehicle
+                            .target_component()
+                            .is_some_and(|c| c.last_parameters_request.is_none()) =>
+                    {
+                        let result = request_parameters(&mut app_state.vehicle);
+                        app_state.report_send(result);
+                        if let Some(component) = app_state.vehicle.target_component_mut() {
+                            component.last_parameters_request = Some(Local::now());
+                        }
+                    }
+                    Screen::Mission
+                        if app_state.vehicle.target_component().is_some_and(|c| {
+                            c.mission_details
+                                .lock()
+                                .unwrap()
+                                .last_mission_request
+                                .is_none()
+                        }) =>
+                    {
+                        let result = request_mission_count(&mut app_state.vehicle);
+                        app_state.report_send(result);
+                        if let Some(component) = app_state.vehicle.target_component() {
+                            component
                                 .mission_details
                                 .lock()
                                 .unwrap()
                                 .last_mission_request = Some(Local::now());
                         }
-                        terminal.draw(|frame| render::draw_mission_screen(app_state, frame))?;
                     }
+                    _ => {}
                 }
+                draw_screen(app_state, terminal)?;
             }
-            AppEvent::Mavlink(mav_frame) => {
-                app_state.vehicle.messages.push(mav_frame.msg.clone());
-                match mav_frame.msg {
-                    mavlink::common::MavMessage::HEARTBEAT(data) => {
-                        let is_armed = data
-                            .base_mode
-                            .contains(MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED);
-                        app_state.vehicle.target_details = Some(TargetDetails {
-                            target_system_id: mav_frame.header.system_id,
-                            target_component_id: mav_frame.header.component_id,
-                        });
-                        app_state.vehicle.is_armed = is_armed;
-                    }
-                    mavlink::common::MavMessage::PARAM_VALUE(data) => {
-                        app_state.vehicle.parameter_messages.push(data);
-                        app_state
-                            .vehicle
-                            .parameter_messages
-                            .sort_by_key(|d| decode_param_id(&d.param_id));
-                    }
-                    mavlink::common::MavMessage::MISSION_COUNT(data) => {
-                        app_state
-                            .vehicle
-                            .mission_details
-                            .lock()
-                            .unwrap()
-                            .mission_items_to_load_num = Some(data.count);
-                        synchronise_mission_items(&app_state.vehicle);
-                    }
-                    mavlink::common::MavMessage::MISSION_ITEM_INT(data) => {
-                        let mut mission_details = app_state.vehicle.mission_details.lock().unwrap();
-                        mission_details.mission_messages.push(data);
-                        mission_details.mission_messages.sort_by_key(|d| d.seq);
-                    }
+            AppEvent::Mavlink(frame) => {
+                handle_frame(app_state, *frame);
 
-                    _ => {}
+                if fps_limiter.check_allowed(SystemTime::now()) {
+                    draw_screen(app_state, terminal)?;
                 }
+            }
+            AppEvent::ReplayRestart => {
+                app_state.restart_replay();
+            }
+            AppEvent::Tick => {
+                let now = app_state.now();
+                let heartbeat_timeout = app_state.heartbeat_timeout();
+                app_state.vehicle.auto_select_target(
+                    app_state.args.target_system,
+                    app_state.args.target_component,
+                    now,
+                    heartbeat_timeout,
+                );
+                let triggered = app_state.alerts.check_time(now);
+                app_state.ring_bell(triggered);
+                draw_screen(app_state, terminal)?;
+            }
+        }
+    }
+    Ok(())
+}
 
-                if fps_limiter.check_allowed(SystemTime::now()) {
-                    match app_state.screen {
-                        Screen::Status => {
-                            terminal.draw(|frame| render::draw_status_screen(app_state, frame))?;
-                        }
-                        Screen::Messages => {
-                            terminal
-                                .draw(|frame| render::draw_messages_screen(app_state, frame))?;
-                        }
-                        Screen::Parameters => {
-                            terminal
-                                .draw(|frame| render::draw_parameters_screen(app_state, frame))?;
-                        }
-                        Screen::Mission => {
-                            terminal.draw(|frame| render::draw_mission_screen(app_state, frame))?;
-                        }
-                    }
+/// Updates the state with a frame from the link, with or without the interface
+fn handle_frame<M: DialectMessage>(app_state: &mut AppState<M>, frame: ReceivedFrame<M>) {
+    let now = frame.received;
+    // a vehicle heard on several links is handled once, only its link stats count every copy,
+    // frames with rejected signatures are only shown and recorded
+    let is_duplicate = match &frame.content {
+        _ if frame.signature.is_rejected() => false,
+        FrameContent::Message { header, message } => {
+            let component = app_state
+                .vehicle
+                .components
+                .entry((header.system_id, header.component_id))
+                .or_default();
+            component
+                .link_stats
+                .entry(frame.link_id)
+                .or_default()
+                .record(Some(header.sequence), frame.raw.len(), now);
+            component.is_duplicate(RecentMessage {
+                link_id: frame.link_id,
+                sequence: header.sequence,
+                message_id: message.message_id(),
+                payload: frame.payload().unwrap_or_default().to_vec(),
+                time: now,
+            })
+        }
+        FrameContent::Undecodable(_) => false,
+    };
+    if !is_duplicate {
+        app_state.record_frame(&frame, now);
+    }
+    app_state
+        .vehicle
+        .link_stats
+        .record(None, frame.raw.len(), now);
+    *app_state
+        .vehicle
+        .signature_stats
+        .entry(frame.signature)
+        .or_default() += 1;
+    match &frame.content {
+        FrameContent::Message { header, message } => {
+            if !is_duplicate && !frame.signature.is_rejected() {
+                handle_message(app_state, header, message, now);
+            }
+        }
+        FrameContent::Undecodable(reason) => {
+            *app_state
+                .vehicle
+                .undecodable_frames
+                .entry(*reason)
+                .or_default() += 1;
+        }
+    }
+    app_state.vehicle.messages.push(frame);
+}
+
+fn handle_message<M: DialectMessage>(
+    app_state: &mut AppState<M>,
+    header: &MavHeader,
+    message: &M,
+    now: DateTime<Local>,
+) {
+    app_state.plot.record(header, message, now);
+    app_state.watch.record(header, message, now);
+    let triggered =
+        app_state.alerts.check_message(header, message, now) + app_state.alerts.check_time(now);
+    app_state.ring_bell(triggered);
+    let heartbeat_timeout = app_state.heartbeat_timeout();
+    let component_key = (header.system_id, header.component_id);
+    let component = app_state
+        .vehicle
+        .components
+        .entry(component_key)
+        .or_default();
+    component.messages_num += 1;
+    component.last_seen = Some(now);
+    component.stream_rates.record(message, now);
+    match dialect::to_common(message) {
+        Some(mavlink::common::MavMessage::HEARTBEAT(data)) => {
+            component.heartbeat = Some(data);
+            component.last_heartbeat = Some(now);
+            app_state.vehicle.auto_select_target(
+                app_state.args.target_system,
+                app_state.args.target_component,
+                now,
+                heartbeat_timeout,
+            );
+            if let Some(rate_hz) = app_state.args.stream_rate
+                && app_state
+                    .vehicle
+                    .target_component()
+                    .is_some_and(|c| !c.data_streams_requested)
+            {
+                let result = request_data_streams(&app_state.vehicle, rate_hz);
+                app_state.report_send(result);
+                if let Some(c) = app_state.vehicle.target_component_mut() {
+                    c.data_streams_requested = true;
                 }
             }
         }
+        Some(mavlink::common::MavMessage::STATUSTEXT(data)) => {
+            app_state.vehicle.status_text_log.push(header, &data, now);
+        }
+        Some(mavlink::common::MavMessage::SYS_STATUS(data)) => {
+            component.sys_status = Some(data);
+            component.last_sys_status = Some(now);
+        }
+        Some(mavlink::common::MavMessage::RADIO_STATUS(data)) => {
+            app_state.vehicle.radio_stats.record(&data, now);
+        }
+        Some(mavlink::common::MavMessage::COMMAND_ACK(data)) => {
+            component.last_command_ack = Some(data);
+        }
+        Some(mavlink::common::MavMessage::PARAM_VALUE(data)) => {
+            component.parameter_messages.push(data);
+            component
+                .parameter_messages
+                .sort_by_key(|d| decode_param_id(&d.param_id));
+        }
+        Some(mavlink::common::MavMessage::MISSION_COUNT(data)) => {
+            component
+                .mission_details
+                .lock()
+                .unwrap()
+                .mission_items_to_load_num = Some(data.count);
+            let result = synchronise_mission_items(&app_state.vehicle, component_key);
+            app_state.report_send(result);
+        }
+        Some(mavlink::common::MavMessage::MISSION_ITEM_INT(data)) => {
+            let mut mission_details = component.mission_details.lock().unwrap();
+            mission_details.mission_messages.push(data);
+            mission_details.mission_messages.sort_by_key(|d| d.seq);
+        }
+
+        _ => {}
+    }
+}
+
+fn draw_screen<M: DialectMessage>(
+    app_state: &mut AppState<M>,
+    terminal: &mut DefaultTerminal,
+) -> Result<()> {
+    match app_state.screen {
+        Screen::Status => {
+            terminal.draw(|frame| render::draw_status_screen(app_state, frame))?;
+        }
+        Screen::Messages => {
+            terminal.draw(|frame| render::draw_messages_screen(app_state, frame))?;
+        }
+        Screen::Plot => {
+            terminal.draw(|frame| render::draw_plot_screen(app_state, frame))?;
+        }
+        Screen::Watch => {
+            terminal.draw(|frame| render::draw_watch_screen(app_state, frame))?;
+        }
+        Screen::Console => {
+            terminal.draw(|frame| render::draw_console_screen(app_state, frame))?;
+        }
+        Screen::Alerts => {
+            terminal.draw(|frame| render::draw_alerts_screen(app_state, frame))?;
+        }
+        Screen::Health => {
+            terminal.draw(|frame| render::draw_health_screen(app_state, frame))?;
+        }
+        Screen::Link => {
+            terminal.draw(|frame| render::draw_link_screen(app_state, frame))?;
+        }
+        Screen::Streams => {
+            terminal.draw(|frame| render::draw_streams_screen(app_state, frame))?;
+        }
+        Screen::Parameters => {
+            terminal.draw(|frame| render::draw_parameters_screen(app_state, frame))?;
+        }
+        Screen::Mission => {
+            terminal.draw(|frame| render::draw_mission_screen(app_state, frame))?;
+        }
     }
     Ok(())
 }
 
-fn handle_input_event(app_state: &mut AppState, event: Event) {
+/// Playback controls, true when the key was one of them
+fn handle_replay_key(replay: &Replay, code: KeyCode) -> bool {
+    match code {
+        KeyCode::Char(' ') => replay.toggle_pause(),
+        KeyCode::Char('.') => replay.step(),
+        KeyCode::Char(']') => replay.change_speed(true),
+        KeyCode::Char('[') => replay.change_speed(false),
+        KeyCode::Right => replay.seek(TimeDelta::seconds(REPLAY_SEEK_SECONDS)),
+        KeyCode::Left => replay.seek(TimeDelta::seconds(-REPLAY_SEEK_SECONDS)),
+        _ => return false,
+    }
+    true
+}
+
+fn handle_input_event<M: DialectMessage>(app_state: &mut AppState<M>, event: Event) {
     if let Event::Key(KeyEvent {
         code, modifiers, ..
     }) = event
     {
+        if let Some(replay) = &app_state.replay
+            && handle_replay_key(replay, code)
+        {
+            return;
+        }
         match code {
             KeyCode::Char('q') | KeyCode::Esc => {
                 app_state.is_exit = true;
@@ -271,6 +1082,9 @@ fn handle_input_event(app_state: &mut AppState, event: Event) {
             KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
                 app_state.is_exit = true;
             }
+            KeyCode::Char('r') if modifiers.contains(KeyModifiers::CONTROL) => {
+                app_state.toggle_recording();
+            }
             KeyCode::Char('r') => match app_state.screen {
                 Screen::Parameters => {
                     app_state.clear_parameters();
@@ -280,6 +1094,81 @@ fn handle_input_event(app_state: &mut AppState, event: Event) {
                 }
                 _ => {}
             },
+            KeyCode::Char('e') if app_state.screen == Screen::Messages => {
+                app_state.export_messages(false);
+            }
+            KeyCode::Char('E') if app_state.screen == Screen::Messages => {
+                app_state.export_messages(true);
+            }
+            KeyCode::Char('n') if app_state.screen == Screen::Messages => {
+                app_state.change_plot_field(true);
+            }
+            KeyCode::Char('N') if app_state.screen == Screen::Messages => {
+                app_state.change_plot_field(false);
+            }
+            KeyCode::Char('p') if app_state.screen == Screen::Messages => {
+                app_state.pin_selected_field();
+            }
+            KeyCode::Char('w') if app_state.screen == Screen::Messages => {
+                app_state.watch_selected_field();
+            }
+            KeyCode::Char('l') if app_state.screen == Screen::Messages => {
+                app_state.change_link_filter();
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') if app_state.screen == Screen::Watch => {
+                app_state.watch.change_window(true);
+            }
+            KeyCode::Char('-') if app_state.screen == Screen::Watch => {
+                app_state.watch.change_window(false);
+            }
+            KeyCode::Char('d') | KeyCode::Delete if app_state.screen == Screen::Watch => {
+                app_state.remove_selected_watch();
+            }
+            KeyCode::Char('s') if app_state.screen == Screen::Watch => {
+                app_state.watch_notice = Some(app_state.save_watch_report());
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') if app_state.screen == Screen::Plot => {
+                app_state.plot.change_window(true);
+            }
+            KeyCode::Char('-') if app_state.screen == Screen::Plot => {
+                app_state.plot.change_window(false);
+            }
+            KeyCode::Char('f') if app_state.screen == Screen::Plot => {
+                let now = app_state.now();
+                app_state.plot.toggle_freeze(now);
+            }
+            KeyCode::Char('d') | KeyCode::Delete if app_state.screen == Screen::Plot => {
+                app_state.remove_selected_series();
+            }
+            KeyCode::Char('f') if app_state.screen == Screen::Console => {
+                app_state.vehicle.status_text_log.cycle_severity_filter();
+                app_state.console_table_state.select_first();
+            }
+            KeyCode::Char('s') if app_state.screen == Screen::Console => {
+                app_state.save_console();
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') if app_state.screen == Screen::Streams => {
+                app_state.change_selected_stream_rate(true);
+            }
+            KeyCode::Char('-') if app_state.screen == Screen::Streams => {
+                app_state.change_selected_stream_rate(false);
+            }
+            KeyCode::Char('o') if app_state.screen == Screen::Streams => {
+                if let Some(message_id) = app_state.get_selected_stream_message_id() {
+                    let result = request_message(&app_state.vehicle, message_id);
+                    app_state.report_send(result);
+                }
+            }
+            KeyCode::Char('a') if app_state.screen == Screen::Streams => {
+                let result = request_data_streams(
+                    &app_state.vehicle,
+                    app_state.args.stream_rate.unwrap_or(DEFAULT_STREAM_RATE_HZ),
+                );
+                app_state.report_send(result);
+            }
+            KeyCode::Enter if app_state.screen == Screen::Status => {
+                app_state.select_target();
+            }
             KeyCode::Char('k') | KeyCode::Up => {
                 if let Some(s) = choose_list_state(app_state) {
                     s.select_previous();
@@ -302,29 +1191,41 @@ fn handle_input_event(app_state: &mut AppState, event: Event) {
             }
             KeyCode::PageUp => {
                 if let Some(s) = choose_list_state(app_state) {
-                    s.select(s.selected().map(|x| (x - 20).max(0)));
+                    s.select(s.selected().map(|x| x.saturating_sub(20)));
                 }
             }
 
             KeyCode::PageDown => {
                 let max_len_option = match app_state.screen {
-                    Screen::Status => None,
-                    Screen::Messages => Some(app_state.vehicle.messages.len()),
-                    Screen::Parameters => Some(app_state.vehicle.parameter_messages.len()),
-                    Screen::Mission => Some(
-                        app_state
-                            .vehicle
-                            .mission_details
-                            .lock()
-                            .unwrap()
-                            .mission_messages
-                            .len(),
-                    ),
+                    Screen::Status => Some(app_state.vehicle.components.len()),
+                    Screen::Messages => Some(app_state.shown_messages().count()),
+                    Screen::Plot => Some(app_state.plot.series.len()),
+                    Screen::Watch => Some(app_state.watch.fields.len()),
+                    Screen::Console => Some(app_state.vehicle.status_text_log.filtered().len()),
+                    Screen::Alerts => Some(app_state.alerts.history.len()),
+                    Screen::Health => app_state
+                        .vehicle
+                        .target_component()
+                        .and_then(|c| c.sys_status.as_ref())
+                        .map(|d| health::decode_sensor_health(d).len()),
+                    Screen::Link => Some(app_state.vehicle.sender_links().count()),
+                    Screen::Streams => app_state
+                        .vehicle
+                        .target_component()
+                        .map(|c| c.stream_rates.rows().len()),
+                    Screen::Parameters => app_state
+                        .vehicle
+                        .target_component()
+                        .map(|c| c.parameter_messages.len()),
+                    Screen::Mission => app_state
+                        .vehicle
+                        .target_component()
+                        .map(|c| c.mission_details.lock().unwrap().mission_messages.len()),
                 };
-                if let Some(max_len) = max_len_option {
-                    if let Some(s) = choose_list_state(app_state) {
-                        s.select(s.selected().map(|x| (x + 20).min(max_len)));
-                    }
+                if let Some(max_len) = max_len_option
+                    && let Some(s) = choose_list_state(app_state)
+                {
+                    s.select(s.selected().map(|x| (x + 20).min(max_len)));
                 }
             }
 
@@ -345,11 +1246,201 @@ fn handle_input_event(app_state: &mut AppState, event: Event) {
     }
 }
 
-fn choose_list_state(app_state: &mut AppState) -> Option<&mut TableState> {
+fn choose_list_state<M: DialectMessage>(app_state: &mut AppState<M>) -> Option<&mut TableState> {
     match app_state.screen {
-        Screen::Status => None,
+        Screen::Status => Some(&mut app_state.components_table_state),
         Screen::Messages => Some(&mut app_state.messages_table_state),
+        Screen::Plot => Some(&mut app_state.plot_table_state),
+        Screen::Watch => Some(&mut app_state.watch_table_state),
+        Screen::Console => Some(&mut app_state.console_table_state),
+        Screen::Alerts => Some(&mut app_state.alerts_table_state),
+        Screen::Health => Some(&mut app_state.health_table_state),
+        Screen::Link => Some(&mut app_state.link_table_state),
+        Screen::Streams => Some(&mut app_state.streams_table_state),
         Screen::Parameters => Some(&mut app_state.parameters_table_state),
         Screen::Mission => Some(&mut app_state.mission_table_state),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use chrono::TimeDelta;
+    use clap::Parser;
+    use mavlink::MavHeader;
+    use mavlink::common::ATTITUDE_DATA;
+    use mavlink::common::HEARTBEAT_DATA;
+    use mavlink::common::MavAutopilot;
+    use mavlink::common::MavMessage;
+    use mavlink::common::MavType;
+
+    use crate::AppState;
+    use crate::ComponentDetails;
+    use crate::TargetDetails;
+    use crate::Vehicle;
+    use crate::cli::Args;
+    use crate::dialect::ExtendedMessage;
+    use crate::frame::FrameContent;
+    use crate::frame::ReceivedFrame;
+    use crate::handle_frame;
+    use crate::signing::SignatureStatus;
+
+    #[test]
+    fn test_lost_target_is_replaced() {
+        let heartbeat = HEARTBEAT_DATA {
+            mavtype: MavType::MAV_TYPE_QUADROTOR,
+            autopilot: MavAutopilot::MAV_AUTOPILOT_ARDUPILOTMEGA,
+            ..Default::default()
+        };
+        let start = Local::now();
+        let timeout = TimeDelta::seconds(5);
+        let mut vehicle = Vehicle::<ExtendedMessage<MavMessage>>::default();
+        for (system_id, last_heartbeat) in [(1, start), (2, start + TimeDelta::seconds(4))] {
+            vehicle.components.insert(
+                (system_id, 1),
+                ComponentDetails {
+                    heartbeat: Some(heartbeat.clone()),
+                    last_heartbeat: Some(last_heartbeat),
+                    ..Default::default()
+                },
+            );
+        }
+        let target = |system_id| {
+            Some(TargetDetails {
+                target_system_id: system_id,
+                target_component_id: 1,
+            })
+        };
+
+        vehicle.auto_select_target(None, None, start + TimeDelta::seconds(5), timeout);
+        assert!(vehicle.target_details == target(1));
+
+        let now = start + TimeDelta::seconds(6);
+        assert!(vehicle.components[&(1, 1)].is_lost(now, timeout));
+        vehicle.auto_select_target(None, None, now, timeout);
+        assert!(vehicle.target_details == target(2));
+
+        // nothing else alive, the lost target stays shown
+        let now = start + TimeDelta::seconds(20);
+        vehicle.auto_select_target(None, None, now, timeout);
+        assert!(vehicle.target_details == target(2));
+    }
+
+    #[test]
+    fn test_messages_are_tagged_by_link() {
+        let args = Args::parse_from([
+            "mavlink-debugger",
+            "serial:/dev/null:57600",
+            "udpin:0.0.0.0:0",
+        ]);
+        let mut app_state =
+            AppState::default(args, Vehicle::<ExtendedMessage<MavMessage>>::default());
+        let start = Local::now();
+        // three messages arrive over link 0, link 1 loses the third one and, after its sequence
+        // numbers wrapped, brings another message with the sequence number of the second one
+        for (link_id, sequences) in [(0, [0, 1, 2].as_slice()), (1, [0, 1].as_slice())] {
+            for sequence in sequences {
+                let mut raw = vec![0; 40];
+                raw[1] = 28;
+                raw[6] = (link_id == 1 && *sequence == 1) as u8;
+                let frame = ReceivedFrame {
+                    raw,
+                    received: start + TimeDelta::milliseconds(*sequence as i64),
+                    content: FrameContent::Message {
+                        header: MavHeader {
+                            system_id: 1,
+                            component_id: 1,
+                            sequence: *sequence,
+                        },
+                        message: ExtendedMessage::Compiled(MavMessage::ATTITUDE(
+                            ATTITUDE_DATA::default(),
+                        )),
+                    },
+                    link_id,
+                    signature: SignatureStatus::Unsigned,
+                };
+                handle_frame(&mut app_state, frame);
+            }
+        }
+
+        let senders = app_state.vehicle.sender_links().collect::<Vec<_>>();
+        assert_eq!(senders.len(), 2);
+        for (key, _, stats) in &senders {
+            assert_eq!(*key, (1, 1));
+            assert_eq!(stats.sequence.duplicates, 0);
+        }
+        assert_eq!(senders[0].2.sequence.received, 3);
+        assert_eq!(senders[1].2.sequence.received, 2);
+        // copies from the second link are not handled again
+        assert_eq!(app_state.vehicle.components[&(1, 1)].messages_num, 4);
+
+        assert_eq!(app_state.shown_messages().count(), 5);
+        app_state.change_link_filter();
+        assert_eq!(app_state.shown_messages().count(), 3);
+        app_state.change_link_filter();
+        let indexes = app_state
+            .shown_messages()
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        assert_eq!(indexes, [3, 4]);
+        app_state.messages_table_state.select(Some(1));
+        assert_eq!(app_state.get_selected_message().unwrap().link_id, 1);
+        app_state.change_link_filter();
+        assert_eq!(app_state.link_filter, None);
+    }
+
+    #[test]
+    fn test_source_details() {
+        let alone = Args::parse_from(["mavlink-debugger", "udpin:0.0.0.0:0"]).source_details();
+        assert_eq!(alone.source_system_id, 255);
+        let router = Args::parse_from([
+            "mavlink-debugger",
+            "serial:/dev/null:57600",
+            "udpout:127.0.0.1:14550",
+        ])
+        .source_details();
+        assert_eq!(router.source_system_id, 254);
+        assert_eq!(router.source_component_id, 190);
+        let chosen = Args::parse_from([
+            "mavlink-debugger",
+            "serial:/dev/null:57600",
+            "udpout:127.0.0.1:14550",
+            "--source-system",
+            "250",
+        ])
+        .source_details();
+        assert_eq!(chosen.source_system_id, 250);
+    }
+
+    #[test]
+    fn test_rejected_frames_are_only_shown() {
+        let args = Args::parse_from(["mavlink-debugger", "udpin:0.0.0.0:0"]);
+        let mut app_state =
+            AppState::default(args, Vehicle::<ExtendedMessage<MavMessage>>::default());
+        for signature in [SignatureStatus::Invalid, SignatureStatus::Missing] {
+            let frame = ReceivedFrame {
+                raw: vec![0; 40],
+                received: Local::now(),
+                content: FrameContent::Message {
+                    header: MavHeader {
+                        system_id: 1,
+                        component_id: 1,
+                        sequence: 0,
+                    },
+                    message: ExtendedMessage::Compiled(MavMessage::ATTITUDE(
+                        ATTITUDE_DATA::default(),
+                    )),
+                },
+                link_id: 0,
+                signature,
+            };
+            handle_frame(&mut app_state, frame);
+        }
+        assert_eq!(app_state.vehicle.messages.len(), 2);
+        assert!(app_state.vehicle.components.is_empty());
+        assert_eq!(
+            app_state.vehicle.signature_stats[&SignatureStatus::Missing],
+            1
+        );
+    }
+}
@@ -1,51 +1,266 @@
+use std::io;
+use std::io::ErrorKind;
+use std::io::Write;
+use std::path::Path;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
 use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
 
-use mavlink::common::MavMessage;
-use mavlink::error::MessageReadError;
+use chrono::Local;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use mavlink::MavHeader;
+use mavlink::MavlinkVersion;
+use mavlink::common::MavAutopilot;
+use mavlink::common::MavCmd;
+use mavlink::common::MavDataStream;
+use mavlink::common::MavModeFlag;
+use mavlink::common::MavParamType;
+use mavlink::common::MavState;
+use mavlink::common::MavType;
+use mavlink::error::MessageWriteError;
 
 use crate::AppEvent;
+use crate::SourceDetails;
 use crate::TargetDetails;
 use crate::Vehicle;
+use crate::dataflash;
+use crate::dialect::DialectMessage;
+use crate::dialect::from_common;
+use crate::export::MessageFilter;
+use crate::frame::FrameContent;
+use crate::frame::FrameParser;
+use crate::frame::ReceivedFrame;
+use crate::frame::UndecodableReason;
+use crate::frame::decode_frame;
+use crate::link;
+use crate::link::LinkReader;
+use crate::link::LinkWriter;
+use crate::replay::Replay;
+use crate::signing::Signing;
+use crate::tlog;
+use crate::ulog;
+use crate::utils::mavlink::encode_param_id;
 
-pub fn connect(address: &str, tx: mpsc::Sender<AppEvent>) -> Vehicle {
-    let mut vehicle = Vehicle::default();
-    let connection = mavlink::connect::<mavlink::common::MavMessage>(address).ok();
-    if connection.is_none() {
-        return vehicle;
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Opens every address, the debugger talks to the vehicle on the first one; frames received on
+/// one address are tagged with its index and forwarded to the others when they pass the filter,
+/// without a filter nothing is forwarded; with a signing key received signatures are checked.
+/// Fails with the first address that cannot be opened
+pub fn connect<M: DialectMessage>(
+    addresses: &[String],
+    forward_filter: Option<MessageFilter>,
+    signing: Option<Arc<Signing>>,
+    source_details: SourceDetails,
+    tx: mpsc::Sender<AppEvent<M>>,
+) -> Result<Vehicle<M>> {
+    let mut vehicle = Vehicle {
+        source_details,
+        signing,
+        ..Default::default()
+    };
+    let mut endpoints = vec![];
+    for address in addresses {
+        let endpoint =
+            link::open(address).map_err(|e| eyre!("Could not open {}: {}", address, e))?;
+        endpoints.push(endpoint);
+    }
+    let writers = endpoints
+        .iter()
+        .map(|(_, writer)| writer.clone())
+        .collect::<Vec<_>>();
+    vehicle.connection = writers.first().cloned();
+    for (link_id, (reader, _)) in endpoints.into_iter().enumerate() {
+        let forwarding = forward_filter.clone().map(|filter| Forwarding {
+            writers: writers
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != link_id)
+                .map(|(_, writer)| writer.clone())
+                .collect(),
+            filter,
+        });
+        subscribe(
+            reader,
+            link_id,
+            forwarding,
+            vehicle.signing.clone(),
+            tx.clone(),
+        );
+    }
+    send_heartbeats(&vehicle);
+
+    Ok(vehicle)
+}
+
+/// Other endpoints a link passes its frames on to
+struct Forwarding {
+    writers: Vec<LinkWriter>,
+    filter: MessageFilter,
+}
+
+impl Forwarding {
+    fn forward<M: DialectMessage>(&self, frame: &ReceivedFrame<M>) {
+        let is_allowed = match &frame.content {
+            FrameContent::Message { header, message } => self.filter.matches(header, message),
+            // messages of other dialects pass unless messages are filtered, corrupt frames never do
+            FrameContent::Undecodable(reason) => {
+                *reason == UndecodableReason::UnknownMessageId && self.filter.is_empty()
+            }
+        };
+        if !is_allowed {
+            return;
+        }
+        for writer in &self.writers {
+            // an endpoint that fails to write does not stop the others
+            let _ = writer.lock().unwrap().write_all(&frame.raw);
+        }
     }
-    vehicle.connection = Some(Arc::new(connection.unwrap()));
-    subscribe(&mut vehicle, tx);
-
-    vehicle
-}
-fn subscribe(vehicle: &mut Vehicle, tx: mpsc::Sender<AppEvent>) {
-    let connection = vehicle.connection.as_mut().unwrap().clone();
-    thread::spawn({
-        move || loop {
-            match connection.recv_frame() {
-                Ok(frame) => tx.send(AppEvent::Mavlink(Box::new(frame))).unwrap(),
-                Err(MessageReadError::Io(e)) => {
-                    if e.kind() == std::io::ErrorKind::WouldBlock {
-                        //no messages currently available to receive -- wait a while
-                        thread::sleep(Duration::from_secs(1));
-                        continue;
-                    } else {
-                        println!("recv error: {e:?}");
-                        break;
+}
+/// Address prefixes of files replayed instead of a link
+const LOG_KINDS: [&str; 3] = ["tlog", "bin", "ulg"];
+
+pub fn is_log_address(address: &str) -> bool {
+    address
+        .split_once(':')
+        .is_some_and(|(kind, _)| LOG_KINDS.contains(&kind))
+}
+
+/// Frames of a log file when the address is "tlog:", "bin:" or "ulg:" followed by a path
+pub fn read_log<M: DialectMessage>(address: &str) -> Option<io::Result<Vec<ReceivedFrame<M>>>> {
+    let (kind, path) = address
+        .split_once(':')
+        .filter(|(kind, _)| LOG_KINDS.contains(kind))?;
+    let path = Path::new(path);
+    let frames = match kind {
+        "tlog" => tlog::read(path).map(|entries| {
+            entries
+                .into_iter()
+                .map(|entry| decode_frame(entry.raw, entry.timestamp))
+                .collect()
+        }),
+        "bin" => dataflash::read(path),
+        "ulg" => ulog::read(path),
+        _ => return None,
+    };
+    Some(frames)
+}
+
+/// Vehicle fed from a log file, messages sent to it go nowhere
+pub fn replay<M: DialectMessage>(
+    frames: Vec<ReceivedFrame<M>>,
+    source_details: SourceDetails,
+    tx: mpsc::Sender<AppEvent<M>>,
+) -> (Vehicle<M>, Replay) {
+    let writer: Box<dyn Write + Send> = Box::new(io::sink());
+    let vehicle = Vehicle {
+        source_details,
+        connection: Some(Arc::new(Mutex::new(writer))),
+        ..Default::default()
+    };
+    (vehicle, Replay::start(frames, tx))
+}
+/// Receives frames of one link on its own thread
+fn subscribe<M: DialectMessage>(
+    mut reader: Box<dyn LinkReader>,
+    link_id: usize,
+    forwarding: Option<Forwarding>,
+    signing: Option<Arc<Signing>>,
+    tx: mpsc::Sender<AppEvent<M>>,
+) {
+    thread::spawn(move || {
+        let mut parser = FrameParser::default();
+        let send = |frame: ReceivedFrame<M>| {
+            // a cut frame has no complete signature to check
+            let is_truncated = matches!(
+                frame.content,
+                FrameContent::Undecodable(UndecodableReason::Truncated)
+            );
+            let signature = match &signing {
+                Some(signing) if !is_truncated => signing.check(&frame.raw),
+                _ => frame.signature,
+            };
+            tx.send(AppEvent::Mavlink(Box::new(ReceivedFrame {
+                link_id,
+                signature,
+                ..frame
+            })))
+            .unwrap();
+        };
+        loop {
+            match reader.read_chunk() {
+                Ok(chunk) if chunk.is_empty() => {
+                    if let Some(raw) = parser.take_incomplete() {
+                        send(ReceivedFrame::truncated(raw, Local::now()));
                     }
+                    break;
+                }
+                Ok(chunk) => {
+                    parser.push(&chunk);
+                    while let Some(raw) = parser.next_frame::<M>() {
+                        let frame = decode_frame(raw, Local::now());
+                        if let Some(forwarding) = &forwarding {
+                            forwarding.forward(&frame);
+                        }
+                        send(frame);
+                    }
+                    // a frame never continues in the next datagram
+                    if reader.is_datagram()
+                        && let Some(raw) = parser.take_incomplete()
+                    {
+                        send(ReceivedFrame::truncated(raw, Local::now()));
+                    }
+                }
+                // no bytes arrived within the read timeout
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(e) => {
+                    eprintln!("recv error: {e:?}");
+                    break;
                 }
-                // messages that didn't get through due to parser errors are ignored
-                _ => {}
             }
         }
     });
 }
-pub fn request_parameters(vehicle: &mut Vehicle) {
+/// Announces the debugger as a ground station, autopilots often stream telemetry only to peers that do so
+fn send_heartbeats<M: DialectMessage>(vehicle: &Vehicle<M>) {
+    let connection = vehicle.connection.as_ref().unwrap().clone();
+    let source_details = vehicle.source_details.clone();
+    let sequence = vehicle.sequence.clone();
+    let signing = vehicle.signing.clone();
+    thread::spawn(move || {
+        loop {
+            let heartbeat_message =
+                mavlink::common::MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA {
+                    custom_mode: 0,
+                    mavtype: MavType::MAV_TYPE_GCS,
+                    autopilot: MavAutopilot::MAV_AUTOPILOT_INVALID,
+                    base_mode: MavModeFlag::empty(),
+                    system_status: MavState::MAV_STATE_ACTIVE,
+                    mavlink_version: 3,
+                });
+            // a failed heartbeat is retried on the next tick
+            let _ = send_with_header::<M>(
+                &connection,
+                &source_details,
+                &sequence,
+                signing.as_deref(),
+                &heartbeat_message,
+            );
+            thread::sleep(HEARTBEAT_INTERVAL);
+        }
+    });
+}
+
+pub fn request_parameters<M: DialectMessage>(
+    vehicle: &mut Vehicle<M>,
+) -> Result<(), MessageWriteError> {
     if vehicle.target_details.is_none() {
-        return;
+        return Ok(());
     }
     let TargetDetails {
         target_system_id,
@@ -57,12 +272,75 @@ pub fn request_parameters(vehicle: &mut Vehicle) {
             target_system: target_system_id,
             target_component: target_component_id,
         });
-    send_message(vehicle, param_request_list_message);
+    send_message(vehicle, param_request_list_message)
+}
+
+pub fn request_parameter<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    name: &str,
+) -> Result<(), MessageWriteError> {
+    send_parameter_request(vehicle, encode_param_id(name), -1)
+}
+
+pub fn request_parameter_by_index<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    index: u16,
+) -> Result<(), MessageWriteError> {
+    send_parameter_request(vehicle, [0; 16], index as i16)
+}
+
+fn send_parameter_request<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    param_id: [u8; 16],
+    param_index: i16,
+) -> Result<(), MessageWriteError> {
+    let Some(TargetDetails {
+        target_system_id,
+        target_component_id,
+    }) = vehicle.target_details.clone()
+    else {
+        return Ok(());
+    };
+    let param_request_read_message =
+        mavlink::common::MavMessage::PARAM_REQUEST_READ(mavlink::common::PARAM_REQUEST_READ_DATA {
+            param_index,
+            target_system: target_system_id,
+            target_component: target_component_id,
+            param_id,
+        });
+    send_message(vehicle, param_request_read_message)
+}
+
+/// The type must be the one the vehicle reported, it decides how the value is stored
+pub fn set_parameter<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    name: &str,
+    value: f32,
+    param_type: MavParamType,
+) -> Result<(), MessageWriteError> {
+    let Some(TargetDetails {
+        target_system_id,
+        target_component_id,
+    }) = vehicle.target_details.clone()
+    else {
+        return Ok(());
+    };
+    let param_set_message =
+        mavlink::common::MavMessage::PARAM_SET(mavlink::common::PARAM_SET_DATA {
+            param_value: value,
+            target_system: target_system_id,
+            target_component: target_component_id,
+            param_id: encode_param_id(name),
+            param_type,
+        });
+    send_message(vehicle, param_set_message)
 }
 
-pub fn request_mission_count(vehicle: &mut Vehicle) {
+pub fn request_mission_count<M: DialectMessage>(
+    vehicle: &mut Vehicle<M>,
+) -> Result<(), MessageWriteError> {
     if vehicle.target_details.is_none() {
-        return;
+        return Ok(());
     }
     let TargetDetails {
         target_system_id,
@@ -73,20 +351,23 @@ pub fn request_mission_count(vehicle: &mut Vehicle) {
         mavlink::common::MISSION_REQUEST_LIST_DATA {
             target_system: target_system_id,
             target_component: target_component_id,
+            mission_type: mavlink::common::MavMissionType::MAV_MISSION_TYPE_MISSION,
         },
     );
-    send_message(vehicle, mission_request_list_message);
+    send_message(vehicle, mission_request_list_message)
 }
 
-pub fn synchronise_mission_items(vehicle: &Vehicle) {
-    let mission_details = vehicle.mission_details.lock().unwrap();
-    if vehicle.target_details.is_none() {
-        return;
-    }
-    let TargetDetails {
-        target_system_id,
-        target_component_id,
-    } = vehicle.target_details.clone().unwrap();
+pub fn synchronise_mission_items<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    (target_system_id, target_component_id): (u8, u8),
+) -> Result<(), MessageWriteError> {
+    let Some(component) = vehicle
+        .components
+        .get(&(target_system_id, target_component_id))
+    else {
+        return Ok(());
+    };
+    let mission_details = component.mission_details.lock().unwrap();
 
     if let Some(to_load_num) = mission_details.mission_items_to_load_num {
         let all_loaded = to_load_num as usize == mission_details.mission_messages.len();
@@ -97,15 +378,206 @@ pub fn synchronise_mission_items(vehicle: &Vehicle) {
                         target_system: target_system_id,
                         target_component: target_component_id,
                         seq: i,
+                        mission_type: mavlink::common::MavMissionType::MAV_MISSION_TYPE_MISSION,
                     },
                 );
-                send_message(vehicle, mission_request_int_message);
+                send_message(vehicle, mission_request_int_message)?;
             }
         }
     }
+    Ok(())
 }
 
-fn send_message(vehicle: &Vehicle, message: MavMessage) {
-    let connection = vehicle.connection.as_ref().unwrap().clone();
-    connection.send_default(&message).unwrap();
+/// Tells the vehicle every downloaded item arrived
+pub fn send_mission_ack<M: DialectMessage>(vehicle: &Vehicle<M>) -> Result<(), MessageWriteError> {
+    let Some(TargetDetails {
+        target_system_id,
+        target_component_id,
+    }) = vehicle.target_details.clone()
+    else {
+        return Ok(());
+    };
+    let mission_ack_message =
+        mavlink::common::MavMessage::MISSION_ACK(mavlink::common::MISSION_ACK_DATA {
+            target_system: target_system_id,
+            target_component: target_component_id,
+            mavtype: mavlink::common::MavMissionResult::MAV_MISSION_ACCEPTED,
+            ..Default::default()
+        });
+    send_message(vehicle, mission_ack_message)
+}
+
+/// Starts a mission upload, the vehicle then requests every item
+pub fn send_mission_count<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    count: u16,
+) -> Result<(), MessageWriteError> {
+    let Some(TargetDetails {
+        target_system_id,
+        target_component_id,
+    }) = vehicle.target_details.clone()
+    else {
+        return Ok(());
+    };
+    let mission_count_message =
+        mavlink::common::MavMessage::MISSION_COUNT(mavlink::common::MISSION_COUNT_DATA {
+            count,
+            target_system: target_system_id,
+            target_component: target_component_id,
+            ..Default::default()
+        });
+    send_message(vehicle, mission_count_message)
+}
+
+pub fn send_mission_item<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    item: &mavlink::common::MISSION_ITEM_INT_DATA,
+) -> Result<(), MessageWriteError> {
+    let Some(TargetDetails {
+        target_system_id,
+        target_component_id,
+    }) = vehicle.target_details.clone()
+    else {
+        return Ok(());
+    };
+    let mission_item_message =
+        mavlink::common::MavMessage::MISSION_ITEM_INT(mavlink::common::MISSION_ITEM_INT_DATA {
+            target_system: target_system_id,
+            target_component: target_component_id,
+            ..item.clone()
+        });
+    send_message(vehicle, mission_item_message)
+}
+
+/// Legacy stream request, still the only way to start telemetry on older ArduPilot firmware
+pub fn request_data_streams<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    rate_hz: u16,
+) -> Result<(), MessageWriteError> {
+    if vehicle.target_details.is_none() {
+        return Ok(());
+    }
+    let TargetDetails {
+        target_system_id,
+        target_component_id,
+    } = vehicle.target_details.clone().unwrap();
+
+    let request_data_stream_message = mavlink::common::MavMessage::REQUEST_DATA_STREAM(
+        mavlink::common::REQUEST_DATA_STREAM_DATA {
+            req_message_rate: rate_hz,
+            target_system: target_system_id,
+            target_component: target_component_id,
+            req_stream_id: MavDataStream::MAV_DATA_STREAM_ALL as u8,
+            start_stop: 1,
+        },
+    );
+    send_message(vehicle, request_data_stream_message)
+}
+
+pub fn set_message_interval<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    message_id: u32,
+    interval_us: i32,
+) -> Result<(), MessageWriteError> {
+    send_command_long(
+        vehicle,
+        MavCmd::MAV_CMD_SET_MESSAGE_INTERVAL,
+        [
+            message_id as f32,
+            interval_us as f32,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+        ],
+        0,
+    )
+}
+
+pub fn request_message<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    message_id: u32,
+) -> Result<(), MessageWriteError> {
+    send_command_long(
+        vehicle,
+        MavCmd::MAV_CMD_REQUEST_MESSAGE,
+        [message_id as f32, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        0,
+    )
+}
+
+pub fn send_command_long<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    command: MavCmd,
+    params: [f32; 7],
+    confirmation: u8,
+) -> Result<(), MessageWriteError> {
+    if vehicle.target_details.is_none() {
+        return Ok(());
+    }
+    let TargetDetails {
+        target_system_id,
+        target_component_id,
+    } = vehicle.target_details.clone().unwrap();
+
+    let command_long_message =
+        mavlink::common::MavMessage::COMMAND_LONG(mavlink::common::COMMAND_LONG_DATA {
+            param1: params[0],
+            param2: params[1],
+            param3: params[2],
+            param4: params[3],
+            param5: params[4],
+            param6: params[5],
+            param7: params[6],
+            command,
+            target_system: target_system_id,
+            target_component: target_component_id,
+            confirmation,
+        });
+    send_message(vehicle, command_long_message)
+}
+
+/// Nothing is sent while replaying a log
+fn send_message<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    message: mavlink::common::MavMessage,
+) -> Result<(), MessageWriteError> {
+    let Some(connection) = &vehicle.connection else {
+        return Ok(());
+    };
+    send_with_header::<M>(
+        connection,
+        &vehicle.source_details,
+        &vehicle.sequence,
+        vehicle.signing.as_deref(),
+        &message,
+    )?;
+    Ok(())
+}
+
+/// Sends a common message in the selected dialect, signed when there is a key;
+/// messages the dialect lacks are not sent
+fn send_with_header<M: DialectMessage>(
+    connection: &LinkWriter,
+    source_details: &SourceDetails,
+    sequence: &AtomicU8,
+    signing: Option<&Signing>,
+    message: &mavlink::common::MavMessage,
+) -> Result<usize, MessageWriteError> {
+    let Some(message) = from_common::<M>(message) else {
+        return Ok(0);
+    };
+    let header = MavHeader {
+        system_id: source_details.source_system_id,
+        component_id: source_details.source_component_id,
+        sequence: sequence.fetch_add(1, Ordering::Relaxed),
+    };
+    let mut bytes = vec![];
+    mavlink::write_versioned_msg(&mut bytes, MavlinkVersion::V2, header, &message)?;
+    if let Some(signing) = signing {
+        signing.sign(&mut bytes, M::extra_crc(message.message_id()));
+    }
+    connection.lock().unwrap().write_all(&bytes)?;
+    Ok(bytes.len())
 }
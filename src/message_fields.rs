@@ -0,0 +1,525 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use serde::Serialize;
+use serde::ser;
+use serde::ser::Impossible;
+use serde::ser::SerializeStruct;
+use serde::ser::SerializeTuple;
+
+/// Field value with the meaning given by the message definition
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Signed(i64),
+    Unsigned(u64),
+    Float(f64),
+    Text(String),
+    Enum(String),
+    Flags(Vec<String>),
+    Array(Vec<FieldValue>),
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldValue::Signed(v) => write!(f, "{}", v),
+            FieldValue::Unsigned(v) => write!(f, "{}", v),
+            FieldValue::Float(v) => write!(f, "{}", v),
+            FieldValue::Text(v) => write!(f, "{:?}", v),
+            FieldValue::Enum(v) => write!(f, "{}", v),
+            FieldValue::Flags(flags) if flags.is_empty() => write!(f, "none"),
+            FieldValue::Flags(flags) => write!(f, "{}", flags.join(" | ")),
+            FieldValue::Array(values) => {
+                let values = values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                write!(f, "[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+/// One decoded field of a message, in the order of the message definition
+#[derive(Clone, Debug, PartialEq)]
+pub struct MessageField {
+    pub name: String,
+    /// MAVLink type like uint16_t or float[4], enum name for enums
+    pub type_name: String,
+    pub value: FieldValue,
+    pub units: Option<String>,
+    /// Description of the enum entry the value names
+    pub description: Option<String>,
+}
+
+impl MessageField {
+    /// Value with its units, as shown in message details
+    pub fn formatted_value(&self) -> String {
+        match &self.units {
+            Some(units) => format!("{} {}", self.value, units),
+            None => self.value.to_string(),
+        }
+    }
+
+    /// Raw value of integer and float fields
+    pub fn numeric_value(&self) -> Option<f64> {
+        match self.value {
+            FieldValue::Signed(v) => Some(v as f64),
+            FieldValue::Unsigned(v) => Some(v as f64),
+            FieldValue::Float(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Value converted from units like degE7 or mm to degrees or meters
+    pub fn scaled_value(&self) -> Option<String> {
+        let value = self.numeric_value()?;
+        let (scale, units, precision) = match self.units.as_deref()? {
+            "degE7" => (1e-7, "°", 7),
+            "degE5" => (1e-5, "°", 5),
+            "cdeg" => (0.01, "°", 2),
+            "ddeg" => (0.1, "°", 1),
+            "rad" => (180.0 / std::f64::consts::PI, "°", 1),
+            "mrad" => (0.18 / std::f64::consts::PI, "°", 2),
+            "rad/s" => (180.0 / std::f64::consts::PI, "°/s", 1),
+            "mrad/s" => (0.18 / std::f64::consts::PI, "°/s", 2),
+            "cdeg/s" => (0.01, "°/s", 2),
+            "mm" => (0.001, "m", 3),
+            "cm" => (0.01, "m", 2),
+            "dm" => (0.1, "m", 1),
+            "mm/s" => (0.001, "m/s", 3),
+            "cm/s" => (0.01, "m/s", 2),
+            "dm/s" => (0.1, "m/s", 1),
+            "mV" => (0.001, "V", 3),
+            "cV" => (0.01, "V", 2),
+            "mA" => (0.001, "A", 3),
+            "cA" => (0.01, "A", 2),
+            "mAh" => (0.001, "Ah", 3),
+            "cdegC" => (0.01, "°C", 2),
+            "cPa" => (0.01, "Pa", 2),
+            "mG" => (0.001, "G", 3),
+            "mgauss" => (0.001, "G", 3),
+            "mG/s" => (0.001, "G/s", 3),
+            "c%" => (0.01, "%", 2),
+            "d%" => (0.1, "%", 1),
+            "ms" => (0.001, "s", 3),
+            "us" => (1e-6, "s", 6),
+            _ => return None,
+        };
+        Some(format!("{:.*}{}", precision, value * scale, units))
+    }
+}
+
+/// Fields of a compiled message in wire order, read through its serde serialization;
+/// `RuntimeDialect::annotate` puts them in declaration order
+pub fn message_fields<T: Serialize>(message: &T) -> Vec<MessageField> {
+    match message.serialize(FieldSerializer) {
+        // messages are tagged with their name in the "type" field
+        Ok(Serialized::Struct(_, fields)) => {
+            fields.into_iter().filter(|f| f.name != "type").collect()
+        }
+        _ => vec![],
+    }
+}
+
+enum Serialized {
+    Value(FieldValue, String),
+    Struct(&'static str, Vec<MessageField>),
+}
+
+impl Serialized {
+    fn into_field(self, name: &str) -> MessageField {
+        let (value, type_name) = match self {
+            Serialized::Value(value, type_name) => (value, type_name),
+            // enums of the mavlink crate are structs tagged with the variant name
+            Serialized::Struct(enum_name, mut fields)
+                if fields.len() == 1 && fields[0].name == "type" =>
+            {
+                let variant = match fields.remove(0).value {
+                    FieldValue::Text(variant) => variant,
+                    other => other.to_string(),
+                };
+                (FieldValue::Enum(variant), enum_name.to_string())
+            }
+            Serialized::Struct(name, fields) => {
+                let values = fields.into_iter().map(|f| f.value).collect::<Vec<_>>();
+                (FieldValue::Array(values), name.to_string())
+            }
+        };
+        MessageField {
+            name: name.to_string(),
+            type_name,
+            value,
+            units: None,
+            description: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Error(String);
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+fn unsupported(what: &str) -> Error {
+    Error(format!("{} is not a MAVLink field", what))
+}
+
+struct FieldSerializer;
+
+struct StructSerializer {
+    name: &'static str,
+    fields: Vec<MessageField>,
+}
+
+struct ArraySerializer {
+    values: Vec<FieldValue>,
+    type_name: String,
+}
+
+fn value(value: FieldValue, type_name: &str) -> Result<Serialized, Error> {
+    Ok(Serialized::Value(value, type_name.to_string()))
+}
+
+impl ser::Serializer for FieldSerializer {
+    type Ok = Serialized;
+    type Error = Error;
+    type SerializeSeq = ArraySerializer;
+    type SerializeTuple = ArraySerializer;
+    type SerializeTupleStruct = Impossible<Serialized, Error>;
+    type SerializeTupleVariant = Impossible<Serialized, Error>;
+    type SerializeMap = Impossible<Serialized, Error>;
+    type SerializeStruct = StructSerializer;
+    type SerializeStructVariant = Impossible<Serialized, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Serialized, Error> {
+        value(FieldValue::Unsigned(v as u64), "bool")
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Serialized, Error> {
+        value(FieldValue::Signed(v as i64), "int8_t")
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Serialized, Error> {
+        value(FieldValue::Signed(v as i64), "int16_t")
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Serialized, Error> {
+        value(FieldValue::Signed(v as i64), "int32_t")
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Serialized, Error> {
+        value(FieldValue::Signed(v), "int64_t")
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Serialized, Error> {
+        value(FieldValue::Unsigned(v as u64), "uint8_t")
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Serialized, Error> {
+        value(FieldValue::Unsigned(v as u64), "uint16_t")
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Serialized, Error> {
+        value(FieldValue::Unsigned(v as u64), "uint32_t")
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Serialized, Error> {
+        value(FieldValue::Unsigned(v), "uint64_t")
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Serialized, Error> {
+        value(FieldValue::Float(v as f64), "float")
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Serialized, Error> {
+        value(FieldValue::Float(v), "double")
+    }
+
+    fn serialize_char(self, v: char) -> Result<Serialized, Error> {
+        value(FieldValue::Text(v.to_string()), "char")
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Serialized, Error> {
+        value(FieldValue::Text(v.to_string()), "string")
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Serialized, Error> {
+        let values = v.iter().map(|b| FieldValue::Unsigned(*b as u64)).collect();
+        value(FieldValue::Array(values), &format!("uint8_t[{}]", v.len()))
+    }
+
+    fn serialize_none(self) -> Result<Serialized, Error> {
+        Err(unsupported("none"))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Serialized, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Serialized, Error> {
+        Err(unsupported("unit"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Serialized, Error> {
+        Ok(Serialized::Struct(name, vec![]))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Serialized, Error> {
+        value(FieldValue::Enum(variant.to_string()), name)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Serialized, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<Serialized, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ArraySerializer, Error> {
+        Ok(ArraySerializer {
+            values: Vec::with_capacity(len.unwrap_or_default()),
+            type_name: String::new(),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ArraySerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(unsupported("tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported("tuple variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(unsupported("map"))
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<StructSerializer, Error> {
+        Ok(StructSerializer {
+            name,
+            fields: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(unsupported("struct variant"))
+    }
+}
+
+impl SerializeStruct for StructSerializer {
+    type Ok = Serialized;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let field = match value.serialize(FieldSerializer)? {
+            // strings other than the "type" tags are bitmasks like "FLAG_A | FLAG_B"
+            Serialized::Value(FieldValue::Text(text), _) if key != "type" => {
+                let flags = text
+                    .split('|')
+                    .map(|flag| flag.trim().to_string())
+                    .filter(|flag| !flag.is_empty())
+                    .collect();
+                Serialized::Value(FieldValue::Flags(flags), "bitmask".to_string())
+            }
+            serialized => serialized,
+        };
+        self.fields.push(field.into_field(key));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Serialized, Error> {
+        Ok(Serialized::Struct(self.name, self.fields))
+    }
+}
+
+impl SerializeTuple for ArraySerializer {
+    type Ok = Serialized;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let field = value.serialize(FieldSerializer)?.into_field("");
+        self.type_name = field.type_name;
+        self.values.push(field.value);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Serialized, Error> {
+        let type_name = format!("{}[{}]", self.type_name, self.values.len());
+        value(FieldValue::Array(self.values), &type_name)
+    }
+}
+
+impl ser::SerializeSeq for ArraySerializer {
+    type Ok = Serialized;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Serialized, Error> {
+        SerializeTuple::end(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::Message;
+    use mavlink::common::ATTITUDE_QUATERNION_DATA;
+    use mavlink::common::HEARTBEAT_DATA;
+    use mavlink::common::MavAutopilot;
+    use mavlink::common::MavMessage;
+    use mavlink::common::MavModeFlag;
+    use mavlink::common::MavType;
+    use mavlink::common::PARAM_VALUE_DATA;
+
+    use crate::message_fields::FieldValue;
+    use crate::message_fields::MessageField;
+    use crate::message_fields::message_fields;
+    use crate::runtime_dialect::RuntimeDialect;
+
+    #[test]
+    fn test_message_fields() {
+        let heartbeat = MavMessage::HEARTBEAT(HEARTBEAT_DATA {
+            custom_mode: 4,
+            mavtype: MavType::MAV_TYPE_QUADROTOR,
+            autopilot: MavAutopilot::MAV_AUTOPILOT_ARDUPILOTMEGA,
+            base_mode: MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED
+                | MavModeFlag::MAV_MODE_FLAG_CUSTOM_MODE_ENABLED,
+            ..Default::default()
+        });
+        let dialect = RuntimeDialect::standard("common.xml").unwrap();
+        let fields = dialect.annotate(heartbeat.message_id(), message_fields(&heartbeat));
+        let names = fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+        // declaration order, the wire order starts with custom_mode
+        assert_eq!(
+            names,
+            [
+                "mavtype",
+                "autopilot",
+                "base_mode",
+                "custom_mode",
+                "system_status",
+                "mavlink_version"
+            ]
+        );
+        assert_eq!(fields[3].type_name, "uint32_t");
+        assert_eq!(fields[3].value, FieldValue::Unsigned(4));
+        assert_eq!(fields[0].type_name, "MavType");
+        assert_eq!(
+            fields[0].value,
+            FieldValue::Enum("MAV_TYPE_QUADROTOR".to_string())
+        );
+        assert_eq!(
+            fields[2].value.to_string(),
+            "MAV_MODE_FLAG_SAFETY_ARMED | MAV_MODE_FLAG_CUSTOM_MODE_ENABLED"
+        );
+
+        let mut param_id = [0; 16];
+        param_id[..9].copy_from_slice(b"RATE_ROLL");
+        let param_value = MavMessage::PARAM_VALUE(PARAM_VALUE_DATA {
+            param_id,
+            ..Default::default()
+        });
+        let fields = dialect.annotate(param_value.message_id(), message_fields(&param_value));
+        let names = fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "param_id",
+                "param_value",
+                "param_type",
+                "param_count",
+                "param_index"
+            ]
+        );
+        assert_eq!(fields[0].value, FieldValue::Text("RATE_ROLL".to_string()));
+    }
+
+    #[test]
+    fn test_array_fields() {
+        let attitude = MavMessage::ATTITUDE_QUATERNION(ATTITUDE_QUATERNION_DATA {
+            q1: 1.0,
+            repr_offset_q: [0.5, 0.0, 0.0, 0.0],
+            ..Default::default()
+        });
+        let fields = message_fields(&attitude);
+        let offset = fields.iter().find(|f| f.name == "repr_offset_q").unwrap();
+        assert_eq!(offset.type_name, "float[4]");
+        assert_eq!(offset.value.to_string(), "[0.5, 0, 0, 0]");
+    }
+
+    #[test]
+    fn test_scaled_value() {
+        let field = |value, units: &str| MessageField {
+            name: String::new(),
+            type_name: String::new(),
+            value,
+            units: Some(units.to_string()),
+            description: None,
+        };
+        assert_eq!(
+            field(FieldValue::Signed(473977419), "degE7").scaled_value(),
+            Some("47.3977419°".to_string())
+        );
+        assert_eq!(
+            field(FieldValue::Signed(12340), "mm").scaled_value(),
+            Some("12.340m".to_string())
+        );
+        assert_eq!(
+            field(FieldValue::Float(0.0907571), "rad").scaled_value(),
+            Some("5.2°".to_string())
+        );
+        assert_eq!(field(FieldValue::Float(3.0), "m").scaled_value(), None);
+    }
+}
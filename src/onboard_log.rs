@@ -0,0 +1,51 @@
+use chrono::DateTime;
+use chrono::Local;
+use chrono::TimeDelta;
+use chrono::TimeZone;
+use mavlink::MavHeader;
+
+use crate::dialect::DialectMessage;
+use crate::frame::FrameContent;
+use crate::frame::ReceivedFrame;
+use crate::runtime_dialect::MessageDefinition;
+use crate::signing::SignatureStatus;
+
+/// Records of onboard logs are shown as messages of the autopilot
+const LOG_SYSTEM_ID: u8 = 1;
+const LOG_COMPONENT_ID: u8 = 1;
+
+/// Log time since boot, shown as a time of day starting at 00:00:00
+pub fn boot_time(micros: u64) -> DateTime<Local> {
+    let since_boot = TimeDelta::microseconds(i64::try_from(micros).unwrap_or(i64::MAX));
+    let epoch = DateTime::UNIX_EPOCH.naive_utc();
+    let time = epoch.checked_add_signed(since_boot).unwrap_or(epoch);
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&time))
+}
+
+/// Record of an onboard log decoded with a definition built from the log itself
+pub fn log_frame<M: DialectMessage>(
+    definition: &'static MessageDefinition,
+    payload: Vec<u8>,
+    raw: Vec<u8>,
+    micros: u64,
+    sequence: u8,
+) -> ReceivedFrame<M> {
+    ReceivedFrame {
+        raw,
+        received: boot_time(micros),
+        content: FrameContent::Message {
+            // sequence numbers keep the Link screen free of made up losses
+            header: MavHeader {
+                system_id: LOG_SYSTEM_ID,
+                component_id: LOG_COMPONENT_ID,
+                sequence,
+            },
+            message: M::runtime(definition, payload),
+        },
+        link_id: 0,
+        signature: SignatureStatus::Unsigned,
+    }
+}
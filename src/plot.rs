@@ -0,0 +1,273 @@
+use std::collections::VecDeque;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::TimeDelta;
+use mavlink::MavHeader;
+
+use crate::dialect::DialectMessage;
+use crate::message_fields::MessageField;
+
+/// Time windows in seconds, changed with (+/-) on the plot screen
+const WINDOWS: [i64; 7] = [5, 10, 30, 60, 120, 300, 600];
+const DEFAULT_WINDOW_INDEX: usize = 2;
+/// Points kept per series on top of the longest window limit
+const MAX_POINTS: usize = 50_000;
+
+/// Numeric field of a message from one component, with the values received so far
+pub struct PlotSeries {
+    pub system_id: u8,
+    pub component_id: u8,
+    pub message_name: String,
+    pub field_name: String,
+    pub units: Option<String>,
+    pub points: VecDeque<(DateTime<Local>, f64)>,
+}
+
+impl PlotSeries {
+    pub fn new(header: &MavHeader, message_name: &str, field: &MessageField) -> Self {
+        PlotSeries {
+            system_id: header.system_id,
+            component_id: header.component_id,
+            message_name: message_name.to_string(),
+            field_name: field.name.clone(),
+            units: field.units.clone(),
+            points: VecDeque::new(),
+        }
+    }
+
+    /// Name shown in the legend, like ATTITUDE.roll
+    pub fn name(&self) -> String {
+        format!("{}.{}", self.message_name, self.field_name)
+    }
+
+    pub fn matches(&self, header: &MavHeader, message_name: &str) -> bool {
+        self.system_id == header.system_id
+            && self.component_id == header.component_id
+            && self.message_name == message_name
+    }
+
+    /// Adds the value of the series field from the decoded fields of its message
+    pub fn push_field(&mut self, fields: &[MessageField], time: DateTime<Local>) -> Option<f64> {
+        let value = fields
+            .iter()
+            .find(|f| f.name == self.field_name)
+            .and_then(|f| f.numeric_value())?;
+        self.push(time, value);
+        Some(value)
+    }
+
+    /// Adds a value, dropping values older than the longest window
+    fn push(&mut self, time: DateTime<Local>, value: f64) {
+        self.points.push_back((time, value));
+        let oldest = time - TimeDelta::seconds(WINDOWS[WINDOWS.len() - 1]);
+        while self.points.len() > MAX_POINTS
+            || self.points.front().is_some_and(|(t, _)| *t < oldest)
+        {
+            self.points.pop_front();
+        }
+    }
+}
+
+/// Pinned fields and how they are shown
+pub struct Plot {
+    pub series: Vec<PlotSeries>,
+    window_index: usize,
+    /// End of the shown window while frozen
+    pub frozen_at: Option<DateTime<Local>>,
+}
+
+impl Default for Plot {
+    fn default() -> Self {
+        Plot {
+            series: Vec::new(),
+            window_index: DEFAULT_WINDOW_INDEX,
+            frozen_at: None,
+        }
+    }
+}
+
+impl Plot {
+    /// Adds the field as a series, or removes it when already pinned; true when added
+    pub fn toggle_pin(
+        &mut self,
+        header: &MavHeader,
+        message_name: &str,
+        field: &MessageField,
+    ) -> bool {
+        if let Some(index) = self.position(header, message_name, &field.name) {
+            self.series.remove(index);
+            return false;
+        }
+        self.series
+            .push(PlotSeries::new(header, message_name, field));
+        true
+    }
+
+    pub fn is_pinned(&self, header: &MavHeader, message_name: &str, field_name: &str) -> bool {
+        self.position(header, message_name, field_name).is_some()
+    }
+
+    fn position(&self, header: &MavHeader, message_name: &str, field_name: &str) -> Option<usize> {
+        self.series
+            .iter()
+            .position(|s| s.matches(header, message_name) && s.field_name == field_name)
+    }
+
+    /// Adds the values of pinned fields, messages without pinned fields are not decoded
+    pub fn record<M: DialectMessage>(
+        &mut self,
+        header: &MavHeader,
+        message: &M,
+        now: DateTime<Local>,
+    ) {
+        let message_name = message.message_name();
+        if !self.series.iter().any(|s| s.matches(header, message_name)) {
+            return;
+        }
+        let fields = message.fields();
+        for series in self
+            .series
+            .iter_mut()
+            .filter(|s| s.matches(header, message_name))
+        {
+            series.push_field(&fields, now);
+        }
+    }
+
+    pub fn clear_points(&mut self) {
+        for series in &mut self.series {
+            series.points.clear();
+        }
+        self.frozen_at = None;
+    }
+
+    pub fn window(&self) -> TimeDelta {
+        TimeDelta::seconds(WINDOWS[self.window_index])
+    }
+
+    pub fn change_window(&mut self, is_longer: bool) {
+        self.window_index = if is_longer {
+            (self.window_index + 1).min(WINDOWS.len() - 1)
+        } else {
+            self.window_index.saturating_sub(1)
+        };
+    }
+
+    pub fn toggle_freeze(&mut self, now: DateTime<Local>) {
+        self.frozen_at = match self.frozen_at {
+            Some(_) => None,
+            None => Some(now),
+        };
+    }
+
+    /// End of the shown window, the freeze time while frozen
+    pub fn end(&self, now: DateTime<Local>) -> DateTime<Local> {
+        self.frozen_at.unwrap_or(now)
+    }
+
+    /// Points inside the window ending at `end`, x in seconds relative to it
+    pub fn visible_points(&self, series: &PlotSeries, end: DateTime<Local>) -> Vec<(f64, f64)> {
+        let start = end - self.window();
+        series
+            .points
+            .iter()
+            .filter(|(t, _)| *t >= start && *t <= end)
+            .map(|(t, v)| ((*t - end).num_milliseconds() as f64 / 1000.0, *v))
+            .collect()
+    }
+
+    /// Y range fitting all visible values with some padding
+    pub fn y_bounds(points: &[Vec<(f64, f64)>]) -> [f64; 2] {
+        let values = points
+            .iter()
+            .flatten()
+            .map(|(_, v)| *v)
+            .filter(|v| v.is_finite());
+        let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(v), max.max(v))
+        });
+        if min > max {
+            return [-1.0, 1.0];
+        }
+        let padding = if max > min {
+            (max - min) * 0.05
+        } else {
+            (min.abs() * 0.1).max(1.0)
+        };
+        [min - padding, max + padding]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use chrono::TimeDelta;
+    use mavlink::MavHeader;
+    use mavlink::common::ATTITUDE_DATA;
+    use mavlink::common::MavMessage;
+    use mavlink::common::VFR_HUD_DATA;
+
+    use crate::dialect::DialectMessage;
+    use crate::dialect::ExtendedMessage;
+    use crate::plot::Plot;
+
+    fn attitude(roll: f32) -> ExtendedMessage<MavMessage> {
+        ExtendedMessage::Compiled(MavMessage::ATTITUDE(ATTITUDE_DATA {
+            roll,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn test_record_pinned_field() {
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 0,
+        };
+        let mut plot = Plot::default();
+        let roll = attitude(0.0)
+            .fields()
+            .into_iter()
+            .find(|f| f.name == "roll")
+            .unwrap();
+        assert!(plot.toggle_pin(&header, "ATTITUDE", &roll));
+
+        let start = Local::now();
+        for i in 0..40 {
+            let now = start + TimeDelta::seconds(i);
+            plot.record(&header, &attitude(i as f32), now);
+            let hud = ExtendedMessage::Compiled(MavMessage::VFR_HUD(VFR_HUD_DATA::default()));
+            plot.record(&header, &hud, now);
+        }
+        let other_component = MavHeader {
+            component_id: 2,
+            ..header
+        };
+        plot.record(&other_component, &attitude(100.0), start);
+
+        let series = &plot.series[0];
+        assert_eq!(series.name(), "ATTITUDE.roll");
+        assert_eq!(series.points.len(), 40);
+        let end = start + TimeDelta::seconds(39);
+        let points = plot.visible_points(series, end);
+        assert_eq!(points.len(), 31);
+        assert_eq!(points[0], (-30.0, 9.0));
+        assert_eq!(points[30], (0.0, 39.0));
+        let [min, max] = Plot::y_bounds(&[points]);
+        assert!(min < 9.0 && max > 39.0);
+
+        plot.change_window(false);
+        assert_eq!(plot.visible_points(&plot.series[0], end).len(), 11);
+
+        assert!(!plot.toggle_pin(&header, "ATTITUDE", &roll));
+        assert!(plot.series.is_empty());
+    }
+
+    #[test]
+    fn test_y_bounds_of_flat_series() {
+        assert_eq!(Plot::y_bounds(&[vec![(0.0, 5.0), (1.0, 5.0)]]), [4.0, 6.0]);
+        assert_eq!(Plot::y_bounds(&[]), [-1.0, 1.0]);
+    }
+}
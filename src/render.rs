@@ -1,6 +1,9 @@
-use mavlink::Message;
+use chrono::DateTime;
+use chrono::Local;
+use chrono::TimeDelta;
 use mavlink::common::MISSION_ITEM_INT_DATA;
 use mavlink::common::MavMessage;
+use mavlink::common::MavSeverity;
 use mavlink::common::PARAM_VALUE_DATA;
 use ratatui::Frame;
 use ratatui::layout::Constraint;
@@ -9,29 +12,55 @@ use ratatui::layout::Rect;
 use ratatui::style::Color;
 use ratatui::style::Style;
 use ratatui::style::Stylize;
+use ratatui::symbols::Marker;
 use ratatui::text::Line;
 use ratatui::text::Span;
 use ratatui::text::Text;
+use ratatui::widgets::Axis;
 use ratatui::widgets::Block;
 use ratatui::widgets::Cell;
+use ratatui::widgets::Chart;
+use ratatui::widgets::Dataset;
+use ratatui::widgets::GraphType;
+use ratatui::widgets::LegendPosition;
 use ratatui::widgets::List;
 use ratatui::widgets::Padding;
 use ratatui::widgets::Paragraph;
 use ratatui::widgets::Row;
+use ratatui::widgets::Sparkline;
 use ratatui::widgets::Table;
 use ratatui::widgets::Tabs;
 use ratatui::widgets::Widget;
 use ratatui::widgets::Wrap;
-use serde_json::Value;
 
 use crate::AppState;
 use crate::Screen;
+use crate::Vehicle;
+use crate::alerts::Alert;
+use crate::alerts::Alerts;
+use crate::dialect::DialectMessage;
+use crate::dialect::to_common;
+use crate::frame::FrameContent;
+use crate::frame::ReceivedFrame;
+use crate::frame::UndecodableReason;
+use crate::frame::hex_dump;
+use crate::health::SensorHealth;
+use crate::health::decode_sensor_health;
+use crate::health::is_prearm_text;
+use crate::plot::Plot;
+use crate::replay::Replay;
+use crate::signing::SignatureStatus;
+use crate::status_text::StatusTextEntry;
+use crate::status_text::severity_name;
+use crate::streams::StreamRate;
 use crate::utils::mavlink::decode_param_id;
 use crate::utils::mavlink::parse_status_text;
+use crate::watch::WatchList;
+use crate::watch::format_value;
 
 use strum::IntoEnumIterator;
 
-pub fn draw_status_screen(app_state: &AppState, frame: &mut Frame) {
+pub fn draw_status_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
     let area = frame.area();
     let [tab_header, tab_content] =
         Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
@@ -42,7 +71,8 @@ pub fn draw_status_screen(app_state: &AppState, frame: &mut Frame) {
         .border_type(ratatui::widgets::BorderType::Thick)
         .render(tab_content, frame.buffer_mut());
 
-    let [headear_area, _, _help_area] = Layout::vertical([
+    let [headear_area, link_health_area, components_area, help_area] = Layout::vertical([
+        Constraint::Length(3),
         Constraint::Length(3),
         Constraint::Fill(1),
         Constraint::Length(3),
@@ -58,19 +88,32 @@ pub fn draw_status_screen(app_state: &AppState, frame: &mut Frame) {
 
     Paragraph::new(Line::from(vec![
         Span::from(" Address: "),
-        Span::from(app_state.args.address.to_string()),
+        Span::from(if app_state.args.no_forward {
+            app_state.args.addresses.join(", ")
+        } else {
+            app_state.args.addresses.join(" ⇄ ")
+        }),
         if app_state.vehicle.connection.is_some() {
             Span::from(" connected ").green()
         } else {
             Span::from(" not connected ").red()
         },
+        if app_state.vehicle.signing.is_some() {
+            Span::from("signed").green()
+        } else {
+            Span::from("")
+        },
     ]))
     .block(Block::bordered().title(" Connection ".bold()))
     .render(connection_area, frame.buffer_mut());
 
-    Paragraph::new(if app_state.vehicle.connection.is_none() {
+    let target_component = app_state.vehicle.target_component();
+    let lost_target = app_state.lost_target();
+    Paragraph::new(if target_component.is_none() {
         Span::from("Unknown").gray()
-    } else if app_state.vehicle.is_armed {
+    } else if lost_target.is_some() {
+        Span::from(" Lost ").red().bold()
+    } else if target_component.is_some_and(|c| c.is_armed()) {
         Span::from(" Armed ").green()
     } else {
         Span::from(" Disarmed ").red()
@@ -94,13 +137,84 @@ pub fn draw_status_screen(app_state: &AppState, frame: &mut Frame) {
         Span::from(target_system_id).bold().green(),
         Span::from(", target component id: "),
         Span::from(target_component_id).bold().green(),
+        if app_state.vehicle.is_target_locked {
+            Span::from(" (fixed)").gray()
+        } else {
+            Span::from(" (auto)").gray()
+        },
+        match lost_target {
+            Some(silence) => Span::from(format!(" lost {} s ago", silence.num_seconds()))
+                .red()
+                .bold(),
+            None => Span::from(""),
+        },
     ]))
     .block(Block::bordered())
     .centered()
     .render(id_area, frame.buffer_mut());
+
+    create_link_health_paragraph(&app_state.vehicle)
+        .block(Block::bordered().title(" Link health ".bold()))
+        .render(link_health_area, frame.buffer_mut());
+
+    let list_components_widget = create_list_components_widget(
+        &app_state.vehicle,
+        app_state.now(),
+        app_state.heartbeat_timeout(),
+    )
+    .block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(" Components ".bold())
+            .title_bottom(
+                Line::from(format!("Total: {}", app_state.vehicle.components.len()))
+                    .right_aligned(),
+            ),
+    );
+    frame.render_stateful_widget(
+        list_components_widget,
+        components_area,
+        &mut app_state.components_table_state,
+    );
+
+    Paragraph::new(
+        Span::from("(Esc|q) quit | (↑/↓) previous/next | (Enter) set as target | (Tab) change tab")
+            .gray(),
+    )
+    .block(Block::bordered())
+    .centered()
+    .render(help_area, frame.buffer_mut());
 }
 
-pub fn draw_messages_screen(app_state: &mut AppState, frame: &mut Frame) {
+fn create_link_health_paragraph<M: DialectMessage>(vehicle: &Vehicle<M>) -> Paragraph<'static> {
+    let undecodable_num = vehicle.undecodable_frames.values().sum::<usize>();
+    let mut spans = vec![Span::from(format!(
+        " Frames: {} decoded",
+        vehicle.messages.len() - undecodable_num
+    ))];
+    for reason in UndecodableReason::iter() {
+        let count = vehicle
+            .undecodable_frames
+            .get(&reason)
+            .copied()
+            .unwrap_or_default();
+        spans.push(Span::from(" | "));
+        let text = Span::from(format!("{}: {}", reason, count));
+        spans.push(if count > 0 { text.red() } else { text.gray() });
+    }
+    Paragraph::new(Line::from(spans))
+}
+
+fn target_label<M: DialectMessage>(vehicle: &Vehicle<M>) -> String {
+    vehicle
+        .target_details
+        .as_ref()
+        .map_or("no target".to_string(), |t| {
+            format!("{}:{}", t.target_system_id, t.target_component_id)
+        })
+}
+
+pub fn draw_messages_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
     let area = frame.area();
     let [tab_header, tab_content] =
         Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
@@ -118,13 +232,18 @@ pub fn draw_messages_screen(app_state: &mut AppState, frame: &mut Frame) {
     let [list_events_area, details_events_area] =
         Layout::horizontal([Constraint::Min(50), Constraint::Percentage(100)]).areas(events_area);
 
-    let list_events_widget = create_list_events_widget(&app_state.vehicle.messages).block(
+    let is_multilink = app_state.args.addresses.len() > 1;
+    let shown_messages = app_state.shown_messages().collect::<Vec<_>>();
+    let events_title = match app_state.link_filter {
+        Some(link_id) => format!(" Events on {} ", link_label(app_state, link_id)),
+        None => " Events ".to_string(),
+    };
+    let list_events_widget = create_list_events_widget(&shown_messages, is_multilink).block(
         Block::bordered()
             .padding(Padding::horizontal(1))
-            .title(" Events ".bold())
-            .title_bottom(
-                Line::from(format!("Total: {}", &app_state.vehicle.messages.len())).right_aligned(),
-            ),
+            .title(events_title.bold())
+            .title_bottom(Line::from(app_state.export_notice.clone().unwrap_or_default()).gray())
+            .title_bottom(Line::from(format!("Total: {}", shown_messages.len())).right_aligned()),
     );
     frame.render_stateful_widget(
         list_events_widget,
@@ -132,40 +251,99 @@ pub fn draw_messages_screen(app_state: &mut AppState, frame: &mut Frame) {
         &mut app_state.messages_table_state,
     );
 
-    create_event_details_paragraph(app_state.get_selected_message())
+    let plot_field = app_state
+        .get_selected_plot_field()
+        .map(|(header, message_name, field)| {
+            let mut pinned_to = vec![];
+            if app_state
+                .plot
+                .is_pinned(&header, &message_name, &field.name)
+            {
+                pinned_to.push("plotted");
+            }
+            if app_state
+                .watch
+                .is_watched(&header, &message_name, &field.name)
+            {
+                pinned_to.push("watched");
+            }
+            (field.name, pinned_to.join(", "))
+        });
+    let selected_message = app_state.get_selected_message();
+    let details_title = match &selected_message {
+        Some(f) if is_multilink => {
+            format!(" Event details on {} ", link_label(app_state, f.link_id))
+        }
+        _ => " Event details ".to_string(),
+    };
+    create_event_details_paragraph(selected_message, plot_field)
         .block(
             Block::bordered()
                 .padding(Padding::uniform(1))
-                .title(" Event details ".bold()),
+                .title(details_title.bold()),
         )
         .render(details_events_area, frame.buffer_mut());
 
     Paragraph::new(
-        Span::from("(Esc|q) quit | (↑/↓) previous/next | (Home/End) first/last | (Tab) change tab")
-            .gray(),
+        Span::from(
+            "(Esc|q) quit | (↑/↓) previous/next | (Home/End) first/last | (n/N) next/previous field | (p) plot field | (w) watch field | (l) filter link | (e) export all | (E) export selected type | (Tab) change tab",
+        )
+        .gray(),
     )
     .block(Block::bordered())
     .centered()
     .render(help_area, frame.buffer_mut());
 }
 
-fn draw_tabs(tab_header: Rect, app_state: &AppState, frame: &mut Frame) {
-    let tab_index = Screen::iter()
-        .position(|x| x == app_state.screen)
-        .unwrap_or(0);
+pub fn draw_plot_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
+    let area = frame.area();
+    let [tab_header, tab_content] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
 
-    let tab_names = Screen::iter()
-        .map(|x| format!(" {} ", x))
-        .collect::<Vec<String>>();
+    draw_tabs(tab_header, app_state, frame);
 
-    Tabs::new(tab_names)
-        .highlight_style(Style::default().bg(Color::Blue))
-        .select(tab_index)
-        .block(Block::bordered().border_type(ratatui::widgets::BorderType::Thick))
-        .render(tab_header, frame.buffer_mut());
+    Block::bordered()
+        .border_type(ratatui::widgets::BorderType::Thick)
+        .render(tab_content, frame.buffer_mut());
+
+    let [plot_area, help_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
+        .margin(1)
+        .areas(tab_content);
+    let [chart_area, list_series_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Length(48)]).areas(plot_area);
+
+    let plot = &app_state.plot;
+    let end = plot.end(app_state.now());
+    let points = plot
+        .series
+        .iter()
+        .map(|series| plot.visible_points(series, end))
+        .collect::<Vec<_>>();
+    create_chart_widget(plot, &points, end).render(chart_area, frame.buffer_mut());
+
+    let list_series_widget = create_list_series_widget(plot).block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(" Series ".bold()),
+    );
+    frame.render_stateful_widget(
+        list_series_widget,
+        list_series_area,
+        &mut app_state.plot_table_state,
+    );
+
+    Paragraph::new(
+        Span::from(
+            "(Esc|q) quit | (↑/↓) previous/next | (+/-) time window | (f) freeze | (d) remove series | (Tab) change tab",
+        )
+        .gray(),
+    )
+    .block(Block::bordered())
+    .centered()
+    .render(help_area, frame.buffer_mut());
 }
 
-pub fn draw_parameters_screen(app_state: &mut AppState, frame: &mut Frame) {
+pub fn draw_watch_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
     let area = frame.area();
     let [tab_header, tab_content] =
         Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
@@ -176,56 +354,135 @@ pub fn draw_parameters_screen(app_state: &mut AppState, frame: &mut Frame) {
         .border_type(ratatui::widgets::BorderType::Thick)
         .render(tab_content, frame.buffer_mut());
 
-    let [parameters_area, help_area] =
-        Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
-            .margin(1)
-            .areas(tab_content);
+    let [watch_area, help_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
+        .margin(1)
+        .areas(tab_content);
 
-    let [list_parameters_area, details_parameters_area] =
-        Layout::horizontal([Constraint::Min(50), Constraint::Percentage(100)])
-            .areas(parameters_area);
-    let [
-        list_parameters_brief_area,
-        details_parameters_statistics_area,
-    ] = Layout::vertical([Constraint::Fill(1), Constraint::Length(6)]).areas(list_parameters_area);
-    let list_parameters_widget =
-        create_list_parameters_widget(&app_state.vehicle.parameter_messages).block(
-            Block::bordered()
-                .padding(Padding::horizontal(1))
-                .title(" Parameters ".bold()),
-        );
+    let window = app_state.watch.window();
+    let list_watch_widget = create_list_watch_widget(&app_state.watch, app_state.now()).block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(format!(" Watch, last {} s ", window.num_seconds()).bold())
+            .title_bottom(Line::from(app_state.watch_notice.clone().unwrap_or_default()).gray())
+            .title_bottom(
+                Line::from("Min, max, mean and std dev over the window, rate per second")
+                    .gray()
+                    .right_aligned(),
+            ),
+    );
     frame.render_stateful_widget(
-        list_parameters_widget,
-        list_parameters_brief_area,
-        &mut app_state.parameters_table_state,
+        list_watch_widget,
+        watch_area,
+        &mut app_state.watch_table_state,
     );
 
-    List::new(vec![
-        Line::from(format!(
-            "Loaded at: {}",
-            &app_state
-                .vehicle
-                .last_parameters_request
-                .map(|t| t.format("%H:%M:%S").to_string())
-                .unwrap_or("Not loaded".to_string())
-        )),
-        Line::from(format!(
-            "Total:     {}",
-            &app_state.vehicle.parameter_messages.len(),
-        )),
-        Line::from(""),
-        Line::from("Press (r) to refresh"),
+    Paragraph::new(
+        Span::from(
+            "(Esc|q) quit | (↑/↓) previous/next | (+/-) window | (d) remove field | (s) save summary | (Tab) change tab",
+        )
+        .gray(),
+    )
+    .block(Block::bordered())
+    .centered()
+    .render(help_area, frame.buffer_mut());
+}
+
+pub fn draw_console_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
+    let area = frame.area();
+    let [tab_header, tab_content] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+
+    draw_tabs(tab_header, app_state, frame);
+
+    Block::bordered()
+        .border_type(ratatui::widgets::BorderType::Thick)
+        .render(tab_content, frame.buffer_mut());
+
+    let [console_area, help_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
+        .margin(1)
+        .areas(tab_content);
+    let [list_status_texts_area, details_console_statistics_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(6)]).areas(console_area);
+
+    let log = &app_state.vehicle.status_text_log;
+    let status_texts = log.filtered();
+    let list_status_texts_widget = create_list_status_texts_widget(&status_texts).block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(" Status texts ".bold()),
+    );
+    let statistics = List::new(vec![
+        Line::from(vec![
+            Span::from("Severity:  up to "),
+            Span::from(severity_name(log.severity_filter))
+                .style(severity_color(log.severity_filter)),
+        ]),
+        Line::from(format!("Total:     {}", status_texts.len())),
+        Line::from(app_state.console_notice.clone().unwrap_or_default()),
+        Line::from("Press (f) to change severity filter, (s) to save to file"),
     ])
-    .block(Block::bordered().padding(Padding::horizontal(1)))
-    .render(details_parameters_statistics_area, frame.buffer_mut());
+    .block(Block::bordered().padding(Padding::horizontal(1)));
+    frame.render_stateful_widget(
+        list_status_texts_widget,
+        list_status_texts_area,
+        &mut app_state.console_table_state,
+    );
+    statistics.render(details_console_statistics_area, frame.buffer_mut());
 
-    create_parameter_details_paragraph(app_state.get_selected_parameter())
+    Paragraph::new(
+        Span::from("(Esc|q) quit | (↑/↓) previous/next | (Home/End) first/last | (Tab) change tab")
+            .gray(),
+    )
+    .block(Block::bordered())
+    .centered()
+    .render(help_area, frame.buffer_mut());
+}
+
+pub fn draw_alerts_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
+    let area = frame.area();
+    let [tab_header, tab_content] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+
+    draw_tabs(tab_header, app_state, frame);
+
+    Block::bordered()
+        .border_type(ratatui::widgets::BorderType::Thick)
+        .render(tab_content, frame.buffer_mut());
+
+    let [alerts_area, help_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
+        .margin(1)
+        .areas(tab_content);
+    let rules_height = app_state.alerts.rules.len().clamp(1, 10) as u16 + 3;
+    let [list_rules_area, list_history_area] =
+        Layout::vertical([Constraint::Length(rules_height), Constraint::Fill(1)])
+            .areas(alerts_area);
+
+    create_list_rules_widget(&app_state.alerts)
         .block(
             Block::bordered()
-                .padding(Padding::uniform(1))
-                .title(" Parameter details ".bold()),
+                .padding(Padding::horizontal(1))
+                .title(" Rules ".bold())
+                .title_bottom(
+                    Line::from("Set with --alert and --alerts-file")
+                        .gray()
+                        .right_aligned(),
+                ),
         )
-        .render(details_parameters_area, frame.buffer_mut());
+        .render(list_rules_area, frame.buffer_mut());
+
+    let list_history_widget = create_list_alert_history_widget(&app_state.alerts.history).block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(" History ".bold())
+            .title_bottom(
+                Line::from(format!("Total: {}", app_state.alerts.history.len())).right_aligned(),
+            ),
+    );
+    frame.render_stateful_widget(
+        list_history_widget,
+        list_history_area,
+        &mut app_state.alerts_table_state,
+    );
 
     Paragraph::new(
         Span::from("(Esc|q) quit | (↑/↓) previous/next | (Home/End) first/last | (Tab) change tab")
@@ -236,7 +493,7 @@ pub fn draw_parameters_screen(app_state: &mut AppState, frame: &mut Frame) {
     .render(help_area, frame.buffer_mut());
 }
 
-pub fn draw_mission_screen(app_state: &mut AppState, frame: &mut Frame) {
+pub fn draw_health_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
     let area = frame.area();
     let [tab_header, tab_content] =
         Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
@@ -247,45 +504,98 @@ pub fn draw_mission_screen(app_state: &mut AppState, frame: &mut Frame) {
         .border_type(ratatui::widgets::BorderType::Thick)
         .render(tab_content, frame.buffer_mut());
 
-    let [mission_area, help_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
+    let [health_area, help_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
         .margin(1)
         .areas(tab_content);
+    let [list_sensors_area, details_health_area] =
+        Layout::horizontal([Constraint::Length(60), Constraint::Fill(1)]).areas(health_area);
+    let [details_health_statistics_area, list_prearm_area] =
+        Layout::vertical([Constraint::Length(7), Constraint::Fill(1)]).areas(details_health_area);
 
-    let mission_details = app_state.vehicle.mission_details.lock().unwrap();
-    let [list_mission_items_area, details_mission_statistics_area] =
-        Layout::vertical([Constraint::Fill(1), Constraint::Length(6)]).areas(mission_area);
-    let list_mission_items_widget =
-        create_list_mission_items_widget(&mission_details.mission_messages).block(
-            Block::bordered()
-                .padding(Padding::horizontal(1))
-                .title(" Mission items ".bold()),
-        );
-    frame.render_stateful_widget(
-        list_mission_items_widget,
-        list_mission_items_area,
-        &mut app_state.mission_table_state,
+    let component = app_state.vehicle.target_component();
+    let sys_status = component.and_then(|c| c.sys_status.as_ref());
+    let sensors = sys_status.map(decode_sensor_health).unwrap_or_default();
+    let failing_num = sensors.iter().filter(|s| s.is_failing()).count();
+
+    let list_sensors_widget = create_list_sensors_widget(&sensors).block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(format!(" Sensors of {} ", target_label(&app_state.vehicle)).bold()),
     );
 
-    List::new(vec![
+    let statistics = List::new(vec![
         Line::from(format!(
-            "Loaded at: {}",
-            mission_details
-                .last_mission_request
+            "Updated at: {}",
+            component
+                .and_then(|c| c.last_sys_status)
                 .map(|t| t.format("%H:%M:%S").to_string())
-                .unwrap_or("Not loaded".to_string())
+                .unwrap_or("No SYS_STATUS received".to_string())
         )),
+        Line::from(vec![
+            Span::from("Failing:    "),
+            if failing_num == 0 {
+                Span::from("none").green()
+            } else {
+                Span::from(failing_num.to_string()).red().bold()
+            },
+        ]),
         Line::from(format!(
-            "Total:     {} of {}",
-            mission_details.mission_messages.len(),
-            mission_details
-                .mission_items_to_load_num
-                .map_or("unknown".to_string(), |x| { x.to_string() })
+            "Battery:    {}",
+            sys_status.map_or("unknown".to_string(), |d| format!(
+                "{:.2} V, {}%",
+                d.voltage_battery as f32 / 1000.0,
+                d.battery_remaining
+            ))
+        )),
+        Line::from(format!(
+            "Load:       {}",
+            sys_status.map_or("unknown".to_string(), |d| format!(
+                "{:.1}%",
+                d.load as f32 / 10.0
+            ))
+        )),
+        Line::from(format!(
+            "Comm drops: {}",
+            sys_status.map_or("unknown".to_string(), |d| format!(
+                "{:.2}%, errors: {}",
+                d.drop_rate_comm as f32 / 100.0,
+                d.errors_comm
+            ))
         )),
-        Line::from(""),
-        Line::from("Press (r) to refresh"),
     ])
-    .block(Block::bordered().padding(Padding::horizontal(1)))
-    .render(details_mission_statistics_area, frame.buffer_mut());
+    .block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(" System status ".bold()),
+    );
+    frame.render_stateful_widget(
+        list_sensors_widget,
+        list_sensors_area,
+        &mut app_state.health_table_state,
+    );
+    statistics.render(details_health_statistics_area, frame.buffer_mut());
+
+    let prearm_lines = app_state
+        .vehicle
+        .status_text_log
+        .entries()
+        .iter()
+        .rev()
+        .filter(|e| is_prearm_text(&e.text))
+        .map(|e| {
+            Line::from(vec![
+                Span::from(format!("{} ", e.received_at.format("%H:%M:%S"))).gray(),
+                Span::from(e.text.clone()).style(severity_color(e.severity)),
+            ])
+        })
+        .collect::<Vec<_>>();
+    List::new(prearm_lines)
+        .block(
+            Block::bordered()
+                .padding(Padding::horizontal(1))
+                .title(" Pre-arm checks ".bold()),
+        )
+        .render(list_prearm_area, frame.buffer_mut());
 
     Paragraph::new(
         Span::from("(Esc|q) quit | (↑/↓) previous/next | (Home/End) first/last | (Tab) change tab")
@@ -296,106 +606,1179 @@ pub fn draw_mission_screen(app_state: &mut AppState, frame: &mut Frame) {
     .render(help_area, frame.buffer_mut());
 }
 
-fn create_event_details_paragraph(message: Option<MavMessage>) -> Paragraph<'static> {
-    if let Some(m) = message {
-        let mut lines = vec![
-            Line::from(format!("Name: {} ", m.message_name())),
-            Line::from(format!("Id:   {} ", m.message_id())),
-            Line::from(""),
-        ];
-        match &m {
-            MavMessage::HEARTBEAT(data) => {
-                lines.push(Line::from(format!(
-                    "custom_mode:     {:?} ",
-                    data.custom_mode
-                )));
-                lines.push(Line::from(format!("mavtype:         {:?}", data.mavtype)));
-                lines.push(Line::from(format!(
-                    "autopilot:       {:?} ",
-                    data.autopilot
-                )));
-                let base_mode = data
-                    .base_mode
-                    .iter()
-                    .map(|x| format!("{:?}", x))
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                lines.push(Line::from(format!("base_mode:       {}", base_mode)));
-                lines.push(Line::from(format!(
-                    "system_status:   {:?} ",
-                    data.system_status
-                )));
-                lines.push(Line::from(format!(
-                    "mavlink_version: {:?} ",
-                    data.mavlink_version
-                )));
-            }
-            MavMessage::STATUSTEXT(data) => {
-                lines.push(Line::from(format!("severity:     {:?} ", data.severity)));
-                let status_text = parse_status_text(&data.text);
-                lines.push(Line::from(format!("text:         {}", status_text)));
-            }
-            _ => {
-                let l = try_parse_message(&m)
-                    .iter()
-                    .map(|(k, v)| Line::from(format!("{}: {}", k, v)))
-                    .collect::<Vec<_>>();
-                lines.extend(l);
-            }
-        };
-        lines.push(Line::from(""));
-        lines.push(Line::from("---------------------------------"));
-        lines.push(Line::from("Raw Message:"));
-        lines.push(Line::from(format!("{:?} ", m)));
-        Paragraph::new(lines).wrap(Wrap { trim: false })
-    } else {
-        Paragraph::new(Line::from(" Select message "))
-    }
-}
+pub fn draw_link_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
+    let area = frame.area();
+    let [tab_header, tab_content] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
 
-fn create_parameter_details_paragraph(parameter: Option<PARAM_VALUE_DATA>) -> Paragraph<'static> {
-    if let Some(param) = parameter {
-        let lines = vec![
-            Line::from(format!("Id:       {} ", decode_param_id(&param.param_id))),
-            Line::from(format!("Value:    {} ", param.param_value)),
-            Line::from(""),
-            Line::from("---------------------------------"),
-            Line::from("Raw parameter:"),
-            Line::from(format!("{:?} ", param)),
-        ];
-        Paragraph::new(lines).wrap(Wrap { trim: false })
-    } else {
-        Paragraph::new(Line::from(" Select parameter "))
-    }
-}
+    draw_tabs(tab_header, app_state, frame);
 
-fn try_parse_message(message: &MavMessage) -> Vec<(String, String)> {
-    let original = format!("{:?}", message);
-    if let Some(brackets_start) = original.find("{") {
-        if let Some(brackets_end) = original.find("}") {
-            let json_str = &original[brackets_start..brackets_end + 1];
-            let maybe_value = json5::from_str::<Value>(json_str);
-            if let Ok(Value::Object(map)) = maybe_value {
-                return map
-                    .iter()
-                    .map(|(k, v)| (format!("{:<20}", k), format!("{:}", v)))
-                    .collect::<Vec<_>>();
-            }
-        }
-    }
-    vec![]
-}
+    Block::bordered()
+        .border_type(ratatui::widgets::BorderType::Thick)
+        .render(tab_content, frame.buffer_mut());
 
-fn create_list_events_widget(messages: &[MavMessage]) -> Table<'static> {
-    let rows = messages.iter().enumerate().map(|(i, m)| {
-        let cell = Cell::default().content(Line::from(vec![
-            Span::from(format!("{:>4}  ", i)).style(Color::Magenta),
-            Span::from(m.message_name().to_string()),
-        ]));
-        Row::new(vec![cell])
-    });
+    let [list_links_area, charts_area, help_area] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(15),
+        Constraint::Length(3),
+    ])
+    .margin(1)
+    .areas(tab_content);
+    let [traffic_area, radio_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(charts_area);
+    let [messages_chart_area, bytes_chart_area, lost_chart_area] = Layout::vertical([
+        Constraint::Length(5),
+        Constraint::Length(5),
+        Constraint::Length(5),
+    ])
+    .areas(traffic_area);
+    let [
+        radio_statistics_area,
+        rssi_chart_area,
+        remote_rssi_chart_area,
+    ] = Layout::vertical([
+        Constraint::Length(5),
+        Constraint::Length(5),
+        Constraint::Length(5),
+    ])
+    .areas(radio_area);
 
-    Table::new(rows, [Constraint::Fill(1)]).row_highlight_style(Style::default().bg(Color::Blue))
+    let now = app_state.now();
+    let vehicle = &app_state.vehicle;
+    let list_links_widget = create_list_links_widget(vehicle, now).block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(" Links by sender ".bold())
+            .title_bottom(Line::from(signature_summary(vehicle)).gray())
+            .title_bottom(
+                Line::from(format!(
+                    "Link: {:.1} msgs/s, {:.0} bytes/s",
+                    vehicle.link_stats.messages_per_second(now),
+                    vehicle.link_stats.bytes_per_second(now)
+                ))
+                .right_aligned(),
+            ),
+    );
+
+    let selected_sender = app_state
+        .link_table_state
+        .selected()
+        .and_then(|index| vehicle.sender_links().nth(index));
+    let (link_label, link_stats) = match selected_sender {
+        Some(((system_id, component_id), link_id, stats)) => (
+            format!("{}:{} on link {}", system_id, component_id, link_id),
+            stats,
+        ),
+        None => ("link".to_string(), &vehicle.link_stats),
+    };
+    let messages_chart = create_sparkline_widget(
+        format!(" Messages/s of {} ", link_label),
+        link_stats.messages_history(now),
+        messages_chart_area,
+        Color::Green,
+    );
+    let bytes_chart = create_sparkline_widget(
+        format!(" Bytes/s of {} ", link_label),
+        link_stats.bytes_history(now),
+        bytes_chart_area,
+        Color::Cyan,
+    );
+    let lost_chart = create_sparkline_widget(
+        format!(" Lost/s of {} ", link_label),
+        link_stats.lost_history(now),
+        lost_chart_area,
+        Color::Red,
+    );
+
+    let radio_stats = &vehicle.radio_stats;
+    let radio_statistics = List::new(match &radio_stats.last_radio_status {
+        None => vec![Line::from("No RADIO_STATUS received").gray()],
+        Some(d) => vec![
+            Line::from(format!("RSSI:    {} local, {} remote", d.rssi, d.remrssi)),
+            Line::from(format!("Noise:   {} local, {} remote", d.noise, d.remnoise)),
+            Line::from(format!(
+                "Errors:  {} received, {} corrected, tx buffer {}%",
+                d.rxerrors, d.fixed, d.txbuf
+            )),
+        ],
+    })
+    .block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(" Radio ".bold())
+            .title_bottom(
+                Line::from(radio_stats.last_received.map_or(String::new(), |t| {
+                    format!("Updated at: {}", t.format("%H:%M:%S"))
+                }))
+                .right_aligned(),
+            ),
+    );
+    let rssi_chart = create_sparkline_widget(
+        " Local RSSI ".to_string(),
+        radio_stats.rssi_history(now),
+        rssi_chart_area,
+        Color::Yellow,
+    );
+    let remote_rssi_chart = create_sparkline_widget(
+        " Remote RSSI ".to_string(),
+        radio_stats.remote_rssi_history(now),
+        remote_rssi_chart_area,
+        Color::Yellow,
+    );
+
+    frame.render_stateful_widget(
+        list_links_widget,
+        list_links_area,
+        &mut app_state.link_table_state,
+    );
+    messages_chart.render(messages_chart_area, frame.buffer_mut());
+    bytes_chart.render(bytes_chart_area, frame.buffer_mut());
+    lost_chart.render(lost_chart_area, frame.buffer_mut());
+    radio_statistics.render(radio_statistics_area, frame.buffer_mut());
+    rssi_chart.render(rssi_chart_area, frame.buffer_mut());
+    remote_rssi_chart.render(remote_rssi_chart_area, frame.buffer_mut());
+
+    Paragraph::new(
+        Span::from("(Esc|q) quit | (↑/↓) previous/next | (Home/End) first/last | (Tab) change tab")
+            .gray(),
+    )
+    .block(Block::bordered())
+    .centered()
+    .render(help_area, frame.buffer_mut());
+}
+
+pub fn draw_streams_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
+    let area = frame.area();
+    let [tab_header, tab_content] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+
+    draw_tabs(tab_header, app_state, frame);
+
+    Block::bordered()
+        .border_type(ratatui::widgets::BorderType::Thick)
+        .render(tab_content, frame.buffer_mut());
+
+    let [streams_area, help_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
+        .margin(1)
+        .areas(tab_content);
+    let [list_streams_area, details_streams_statistics_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(6)]).areas(streams_area);
+
+    let component = app_state.vehicle.target_component();
+    let stream_rates = component.map(|c| c.stream_rates.rows()).unwrap_or_default();
+    let list_streams_widget = create_list_streams_widget(&stream_rates, app_state.now()).block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(format!(" Message rates of {} ", target_label(&app_state.vehicle)).bold()),
+    );
+    let statistics = List::new(vec![
+        Line::from(format!(
+            "Last command ack: {}",
+            component.and_then(|c| c.last_command_ack.as_ref()).map_or(
+                "none".to_string(),
+                |ack| format!("{:?} {:?}", ack.command, ack.result)
+            )
+        )),
+        Line::from(""),
+        Line::from("Press (+/-) to change the rate of selected message, (o) to request it once"),
+        Line::from("Press (a) to request all legacy data streams"),
+    ])
+    .block(Block::bordered().padding(Padding::horizontal(1)));
+    frame.render_stateful_widget(
+        list_streams_widget,
+        list_streams_area,
+        &mut app_state.streams_table_state,
+    );
+    statistics.render(details_streams_statistics_area, frame.buffer_mut());
+
+    Paragraph::new(
+        Span::from("(Esc|q) quit | (↑/↓) previous/next | (Home/End) first/last | (Tab) change tab")
+            .gray(),
+    )
+    .block(Block::bordered())
+    .centered()
+    .render(help_area, frame.buffer_mut());
+}
+
+fn draw_tabs<M: DialectMessage>(tab_header: Rect, app_state: &AppState<M>, frame: &mut Frame) {
+    let tab_index = Screen::iter()
+        .position(|x| x == app_state.screen)
+        .unwrap_or(0);
+
+    let tab_names = Screen::iter()
+        .map(|x| format!(" {} ", x))
+        .collect::<Vec<String>>();
+
+    let recording = match (&app_state.recorder, &app_state.recording_notice) {
+        (Some(recorder), _) => Line::from(format!(
+            " ● REC {} ({} frames) ",
+            recorder.path.display(),
+            recorder.frames_written
+        ))
+        .red()
+        .bold(),
+        (None, Some(notice)) => Line::from(format!(" {} ", notice)).gray(),
+        (None, None) => Line::from(" (Ctrl+r) record ").gray(),
+    };
+    let mut block = Block::bordered()
+        .border_type(ratatui::widgets::BorderType::Thick)
+        .title_top(recording.right_aligned());
+    if let Some(silence) = app_state.lost_target() {
+        block = block.title_top(
+            Line::from(format!(
+                " Target {} lost {} s ago ",
+                target_label(&app_state.vehicle),
+                silence.num_seconds()
+            ))
+            .white()
+            .on_red()
+            .bold(),
+        );
+    }
+    if let Some(banner) = create_alert_banner(&app_state.alerts) {
+        block = block.title_top(banner);
+    }
+    if let Some(error) = &app_state.send_error {
+        block = block.title_top(
+            Line::from(format!(" Could not send: {} ", error))
+                .white()
+                .on_red(),
+        );
+    }
+    if let Some(replay) = &app_state.replay {
+        block = block
+            .title_bottom(create_timeline_line(replay, tab_header.width / 2))
+            .title_bottom(
+                Line::from(" (Space) pause | (.) step | ([/]) speed | (←/→) seek 10s ".to_string())
+                    .gray()
+                    .right_aligned(),
+            );
+    }
+    Tabs::new(tab_names)
+        .highlight_style(Style::default().bg(Color::Blue))
+        .select(tab_index)
+        .block(block)
+        .render(tab_header, frame.buffer_mut());
+}
+
+/// Play state, speed, position and a progress bar of the replay
+fn create_timeline_line(replay: &Replay, width: u16) -> Line<'static> {
+    let duration = (replay.end - replay.start).num_milliseconds().max(1);
+    let elapsed = (replay.current_time() - replay.start).num_milliseconds();
+    let format_time = |milliseconds: i64| {
+        let seconds = milliseconds / 1000;
+        format!(
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    };
+    let text = format!(
+        " {} {}x {} / {}  frame {} of {} ",
+        if replay.is_paused() { "⏸" } else { "▶" },
+        replay.speed(),
+        format_time(elapsed),
+        format_time(duration),
+        replay.position(),
+        replay.frames,
+    );
+    let bar_width = (width as usize).saturating_sub(text.chars().count() + 2);
+    let done = (bar_width as i64 * elapsed / duration) as usize;
+    Line::from(vec![
+        Span::from(text),
+        Span::from("━".repeat(done.min(bar_width))).blue(),
+        Span::from("─".repeat(bar_width.saturating_sub(done))).gray(),
+        Span::from(" "),
+    ])
+}
+
+pub fn draw_parameters_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
+    let area = frame.area();
+    let [tab_header, tab_content] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+
+    draw_tabs(tab_header, app_state, frame);
+
+    Block::bordered()
+        .border_type(ratatui::widgets::BorderType::Thick)
+        .render(tab_content, frame.buffer_mut());
+
+    let [parameters_area, help_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
+            .margin(1)
+            .areas(tab_content);
+
+    let [list_parameters_area, details_parameters_area] =
+        Layout::horizontal([Constraint::Min(50), Constraint::Percentage(100)])
+            .areas(parameters_area);
+    let [
+        list_parameters_brief_area,
+        details_parameters_statistics_area,
+    ] = Layout::vertical([Constraint::Fill(1), Constraint::Length(6)]).areas(list_parameters_area);
+    let component = app_state.vehicle.target_component();
+    let parameter_messages = component.map_or(&[][..], |c| &c.parameter_messages[..]);
+    let list_parameters_widget = create_list_parameters_widget(parameter_messages).block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(format!(" Parameters of {} ", target_label(&app_state.vehicle)).bold()),
+    );
+    let statistics = List::new(vec![
+        Line::from(format!(
+            "Loaded at: {}",
+            component
+                .and_then(|c| c.last_parameters_request)
+                .map(|t| t.format("%H:%M:%S").to_string())
+                .unwrap_or("Not loaded".to_string())
+        )),
+        Line::from(format!("Total:     {}", parameter_messages.len())),
+        Line::from(""),
+        Line::from("Press (r) to refresh"),
+    ])
+    .block(Block::bordered().padding(Padding::horizontal(1)));
+    frame.render_stateful_widget(
+        list_parameters_widget,
+        list_parameters_brief_area,
+        &mut app_state.parameters_table_state,
+    );
+    statistics.render(details_parameters_statistics_area, frame.buffer_mut());
+
+    create_parameter_details_paragraph(app_state.get_selected_parameter())
+        .block(
+            Block::bordered()
+                .padding(Padding::uniform(1))
+                .title(" Parameter details ".bold()),
+        )
+        .render(details_parameters_area, frame.buffer_mut());
+
+    Paragraph::new(
+        Span::from("(Esc|q) quit | (↑/↓) previous/next | (Home/End) first/last | (Tab) change tab")
+            .gray(),
+    )
+    .block(Block::bordered())
+    .centered()
+    .render(help_area, frame.buffer_mut());
+}
+
+pub fn draw_mission_screen<M: DialectMessage>(app_state: &mut AppState<M>, frame: &mut Frame) {
+    let area = frame.area();
+    let [tab_header, tab_content] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(area);
+
+    draw_tabs(tab_header, app_state, frame);
+
+    Block::bordered()
+        .border_type(ratatui::widgets::BorderType::Thick)
+        .render(tab_content, frame.buffer_mut());
+
+    let [mission_area, help_area] = Layout::vertical([Constraint::Fill(1), Constraint::Length(3)])
+        .margin(1)
+        .areas(tab_content);
+
+    let mission_details = app_state
+        .vehicle
+        .target_component()
+        .map(|c| c.mission_details.lock().unwrap());
+    let mission_messages = mission_details
+        .as_ref()
+        .map_or(&[][..], |d| &d.mission_messages[..]);
+    let [list_mission_items_area, details_mission_statistics_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(6)]).areas(mission_area);
+    let list_mission_items_widget = create_list_mission_items_widget(mission_messages).block(
+        Block::bordered()
+            .padding(Padding::horizontal(1))
+            .title(format!(" Mission items of {} ", target_label(&app_state.vehicle)).bold()),
+    );
+    let statistics = List::new(vec![
+        Line::from(format!(
+            "Loaded at: {}",
+            mission_details
+                .as_ref()
+                .and_then(|d| d.last_mission_request)
+                .map(|t| t.format("%H:%M:%S").to_string())
+                .unwrap_or("Not loaded".to_string())
+        )),
+        Line::from(format!(
+            "Total:     {} of {}",
+            mission_messages.len(),
+            mission_details
+                .as_ref()
+                .and_then(|d| d.mission_items_to_load_num)
+                .map_or("unknown".to_string(), |x| { x.to_string() })
+        )),
+        Line::from(""),
+        Line::from("Press (r) to refresh"),
+    ])
+    .block(Block::bordered().padding(Padding::horizontal(1)));
+    drop(mission_details);
+    frame.render_stateful_widget(
+        list_mission_items_widget,
+        list_mission_items_area,
+        &mut app_state.mission_table_state,
+    );
+    statistics.render(details_mission_statistics_area, frame.buffer_mut());
+
+    Paragraph::new(
+        Span::from("(Esc|q) quit | (↑/↓) previous/next | (Home/End) first/last | (Tab) change tab")
+            .gray(),
+    )
+    .block(Block::bordered())
+    .centered()
+    .render(help_area, frame.buffer_mut());
+}
+
+/// `plot_field` is the field (p) and (w) pin, with the screens it is already pinned to
+fn create_event_details_paragraph<M: DialectMessage>(
+    frame: Option<ReceivedFrame<M>>,
+    plot_field: Option<(String, String)>,
+) -> Paragraph<'static> {
+    let Some(frame) = frame else {
+        return Paragraph::new(Line::from(" Select message "));
+    };
+    let mut lines = match &frame.content {
+        FrameContent::Message { message, .. } => {
+            create_message_details_lines(message.clone(), plot_field)
+        }
+        FrameContent::Undecodable(reason) => vec![
+            Line::from(" Undecodable frame ".red().bold()),
+            Line::from(format!("Reason: {} ", reason)),
+            Line::from(format!(
+                "Id:     {} ",
+                frame
+                    .message_id()
+                    .map_or("unknown".to_string(), |id| id.to_string())
+            )),
+            Line::from(format!(
+                "Length: {} payload bytes, {} frame bytes ",
+                frame
+                    .payload_len()
+                    .map_or("unknown".to_string(), |len| len.to_string()),
+                frame.raw.len()
+            )),
+        ],
+    };
+    if !frame.is_log_record() {
+        let signature = Span::from(frame.signature.to_string());
+        lines.push(Line::from(vec![
+            Span::from("Signature: "),
+            match frame.signature {
+                SignatureStatus::Valid => signature.green(),
+                SignatureStatus::Invalid | SignatureStatus::Replayed => signature.red(),
+                _ => signature,
+            },
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from("---------------------------------"));
+    if !frame.is_log_record() {
+        lines.push(Line::from("Frame:"));
+        lines.extend(create_frame_lines(&frame));
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from("Raw bytes:"));
+    lines.extend(hex_dump(&frame.raw).into_iter().map(Line::from));
+    Paragraph::new(lines).wrap(Wrap { trim: false })
+}
+
+/// Annotated hex dump, 8 bytes per line
+fn create_frame_lines<M: DialectMessage>(frame: &ReceivedFrame<M>) -> Vec<Line<'static>> {
+    frame
+        .fields()
+        .into_iter()
+        .flat_map(|field| {
+            let bytes = &frame.raw[field.bytes];
+            let mut chunks = bytes
+                .chunks(8)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .map(|b| format!("{:02x}", b))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>();
+            if chunks.is_empty() {
+                chunks.push("--".to_string());
+            }
+            let value = if !field.is_valid {
+                Span::from(field.value).red()
+            } else {
+                Span::from(field.value).gray()
+            };
+            let mut lines = vec![Line::from(vec![
+                Span::from(format!("{:<10}", field.name)).cyan(),
+                Span::from(format!("{:<24}", chunks[0])),
+                value,
+            ])];
+            lines.extend(
+                chunks[1..]
+                    .iter()
+                    .map(|chunk| Line::from(format!("{:<10}{}", "", chunk))),
+            );
+            lines
+        })
+        .collect()
+}
+
+fn create_message_details_lines<M: DialectMessage>(
+    m: M,
+    plot_field: Option<(String, String)>,
+) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(format!("Name: {} ", m.message_name())),
+        Line::from(format!("Id:   {} ", m.message_id())),
+    ];
+    if let Some((field_name, pinned_to)) = &plot_field {
+        lines.push(Line::from(vec![
+            Span::from("Pin:  "),
+            Span::from(format!("{}.{} ", m.message_name(), field_name)).cyan(),
+            Span::from(pinned_to.clone()).gray(),
+        ]));
+    }
+    lines.push(Line::from(""));
+    match to_common(&m) {
+        Some(MavMessage::HEARTBEAT(data)) => {
+            lines.push(Line::from(format!(
+                "custom_mode:     {:?} ",
+                data.custom_mode
+            )));
+            lines.push(Line::from(format!("mavtype:         {:?}", data.mavtype)));
+            lines.push(Line::from(format!(
+                "autopilot:       {:?} ",
+                data.autopilot
+            )));
+            let base_mode = data
+                .base_mode
+                .iter()
+                .map(|x| format!("{:?}", x))
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(Line::from(format!("base_mode:       {}", base_mode)));
+            lines.push(Line::from(format!(
+                "system_status:   {:?} ",
+                data.system_status
+            )));
+            lines.push(Line::from(format!(
+                "mavlink_version: {:?} ",
+                data.mavlink_version
+            )));
+        }
+        Some(MavMessage::STATUSTEXT(data)) => {
+            lines.push(Line::from(format!("severity:     {:?} ", data.severity)));
+            let status_text = parse_status_text(&data.text);
+            lines.push(Line::from(format!("text:         {}", status_text)));
+            lines.push(Line::from(format!("id:           {}", data.id)));
+            lines.push(Line::from(format!("chunk_seq:    {}", data.chunk_seq)));
+        }
+        _ => {
+            for field in m.fields() {
+                let is_plot_field = plot_field
+                    .as_ref()
+                    .is_some_and(|(name, _)| *name == field.name);
+                let name = Span::from(format!("{:<20}", field.name));
+                let mut spans = vec![
+                    if is_plot_field { name.on_blue() } else { name },
+                    Span::from(": "),
+                    Span::from(field.formatted_value()),
+                ];
+                if let Some(scaled) = field.scaled_value() {
+                    spans.push(Span::from(format!("  {}", scaled)).cyan());
+                }
+                spans.push(Span::from(format!("  {}", field.type_name)).gray());
+                lines.push(Line::from(spans));
+                if let Some(description) = field.description {
+                    lines.push(Line::from(format!("{:<22}{}", "", description)).gray());
+                }
+            }
+        }
+    };
+    lines.push(Line::from(""));
+    lines.push(Line::from("---------------------------------"));
+    lines.push(Line::from("Raw Message:"));
+    lines.push(Line::from(format!("{:?} ", m)));
+    lines
+}
+
+fn create_parameter_details_paragraph(parameter: Option<PARAM_VALUE_DATA>) -> Paragraph<'static> {
+    if let Some(param) = parameter {
+        let lines = vec![
+            Line::from(format!("Id:       {} ", decode_param_id(&param.param_id))),
+            Line::from(format!("Value:    {} ", param.param_value)),
+            Line::from(""),
+            Line::from("---------------------------------"),
+            Line::from("Raw parameter:"),
+            Line::from(format!("{:?} ", param)),
+        ];
+        Paragraph::new(lines).wrap(Wrap { trim: false })
+    } else {
+        Paragraph::new(Line::from(" Select parameter "))
+    }
+}
+
+fn create_list_components_widget<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    now: DateTime<Local>,
+    heartbeat_timeout: TimeDelta,
+) -> Table<'static> {
+    let header = [
+        "",
+        "System",
+        "Component",
+        "Type",
+        "Autopilot",
+        "Armed",
+        "Heartbeat",
+        "State",
+        "Messages",
+    ]
+    .into_iter()
+    .map(Cell::from)
+    .collect::<Row>()
+    .height(1);
+    let rows = vehicle
+        .components
+        .iter()
+        .map(|((system_id, component_id), c)| {
+            let is_target = vehicle.target_details.as_ref().is_some_and(|t| {
+                t.target_system_id == *system_id && t.target_component_id == *component_id
+            });
+            let heartbeat = c.heartbeat.as_ref();
+            let is_lost = c.is_lost(now, heartbeat_timeout);
+            Row::new(vec![
+                Cell::from(Text::from(if is_target { "*" } else { "" }).green().bold()),
+                Cell::from(Text::from(system_id.to_string()).magenta()),
+                Cell::from(Text::from(component_id.to_string()).magenta()),
+                Cell::from(Text::from(heartbeat.map_or("unknown".to_string(), |h| {
+                    format!("{:?}", h.mavtype).replace("MAV_TYPE_", "")
+                }))),
+                Cell::from(Text::from(heartbeat.map_or("unknown".to_string(), |h| {
+                    format!("{:?}", h.autopilot).replace("MAV_AUTOPILOT_", "")
+                }))),
+                Cell::from(match heartbeat {
+                    None => Text::from("unknown").gray(),
+                    // last known state, the component may have rebooted since
+                    Some(_) if is_lost => {
+                        Text::from(if c.is_armed() { "armed?" } else { "disarmed?" }).gray()
+                    }
+                    Some(_) if c.is_armed() => Text::from("armed").green(),
+                    Some(_) => Text::from("disarmed").red(),
+                }),
+                Cell::from(Text::from(
+                    c.last_heartbeat
+                        .map_or("never".to_string(), |t| t.format("%H:%M:%S").to_string()),
+                )),
+                Cell::from(if is_lost {
+                    Text::from("lost").red().bold()
+                } else {
+                    Text::from("active").green()
+                }),
+                Cell::from(Text::from(c.messages_num.to_string())),
+            ])
+        });
+    Table::new(
+        rows,
+        [
+            Constraint::Length(2),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(24),
+            Constraint::Length(24),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(8),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(Style::default().bg(Color::Blue))
+}
+
+fn create_list_links_widget<M: DialectMessage>(
+    vehicle: &Vehicle<M>,
+    now: DateTime<Local>,
+) -> Table<'static> {
+    let header = [
+        "System",
+        "Component",
+        "Link",
+        "Received",
+        "Lost",
+        "Loss, %",
+        "Duplicates",
+        "Out of order",
+        "Msgs/s",
+        "Bytes/s",
+    ]
+    .into_iter()
+    .map(Cell::from)
+    .collect::<Row>()
+    .height(1);
+    let rows = vehicle
+        .sender_links()
+        .map(|((system_id, component_id), link_id, stats)| {
+            let sequence = &stats.sequence;
+            let loss = Text::from(format!("{:.1}", sequence.loss_percent()));
+            Row::new(vec![
+                Cell::from(Text::from(system_id.to_string()).magenta()),
+                Cell::from(Text::from(component_id.to_string()).magenta()),
+                Cell::from(Text::from(link_id.to_string())),
+                Cell::from(Text::from(sequence.received.to_string())),
+                Cell::from(Text::from(sequence.lost.to_string())),
+                Cell::from(if sequence.lost > 0 { loss.red() } else { loss }),
+                Cell::from(Text::from(sequence.duplicates.to_string())),
+                Cell::from(Text::from(sequence.out_of_order.to_string())),
+                Cell::from(Text::from(format!("{:.1}", stats.messages_per_second(now)))),
+                Cell::from(Text::from(format!("{:.0}", stats.bytes_per_second(now)))),
+            ])
+        });
+    Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(10),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(12),
+            Constraint::Length(14),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(Style::default().bg(Color::Blue))
+}
+
+/// Received frames by signature status, like "Signatures: 120 valid signature, 2 unsigned"
+fn signature_summary<M: DialectMessage>(vehicle: &Vehicle<M>) -> String {
+    if vehicle.signing.is_none()
+        && !vehicle
+            .signature_stats
+            .contains_key(&SignatureStatus::Unchecked)
+    {
+        return String::new();
+    }
+    let counts = vehicle
+        .signature_stats
+        .iter()
+        .map(|(status, count)| format!("{} {}", count, status))
+        .collect::<Vec<_>>();
+    format!("Signatures: {}", counts.join(", "))
+}
+
+/// Index and address of a link, like "link 1 (udpin:0.0.0.0:14550)"
+fn link_label<M: DialectMessage>(app_state: &AppState<M>, link_id: usize) -> String {
+    match app_state.args.addresses.get(link_id) {
+        Some(address) => format!("link {} ({})", link_id, address),
+        None => format!("link {}", link_id),
+    }
+}
+
+/// Sparkline of the most recent values that fit into the area
+fn create_sparkline_widget(
+    title: String,
+    values: Vec<u64>,
+    area: Rect,
+    color: Color,
+) -> Sparkline<'static> {
+    let width = area.width.saturating_sub(2) as usize;
+    let values = values[values.len().saturating_sub(width)..].to_vec();
+    let max = values.iter().max().copied().unwrap_or_default();
+    Sparkline::default()
+        .block(
+            Block::bordered()
+                .title(title.bold())
+                .title_bottom(Line::from(format!("max {}", max)).right_aligned()),
+        )
+        .data(values)
+        .style(Style::default().fg(color))
+}
+
+/// Colors of the plot series, repeated when there are more series
+const SERIES_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Green,
+    Color::Red,
+    Color::Blue,
+];
+
+fn series_color(index: usize) -> Color {
+    SERIES_COLORS[index % SERIES_COLORS.len()]
+}
+
+fn create_chart_widget<'a>(
+    plot: &'a Plot,
+    points: &'a [Vec<(f64, f64)>],
+    end: DateTime<Local>,
+) -> Chart<'a> {
+    let datasets = plot
+        .series
+        .iter()
+        .zip(points)
+        .enumerate()
+        .map(|(index, (series, data))| {
+            Dataset::default()
+                .name(series.name())
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(series_color(index)))
+                .data(data)
+        })
+        .collect::<Vec<_>>();
+    let window = plot.window().num_seconds() as f64;
+    let [min, max] = Plot::y_bounds(points);
+    let title = match plot.frozen_at {
+        Some(_) => format!(" Plot, frozen at {} ", end.format("%H:%M:%S")),
+        None => " Plot ".to_string(),
+    };
+    let placeholder = if plot.series.is_empty() {
+        " Pin fields with (p) on the Messages tab "
+    } else {
+        ""
+    };
+    Chart::new(datasets)
+        .block(
+            Block::bordered()
+                .title(title.bold())
+                .title_bottom(Line::from(placeholder).gray()),
+        )
+        .x_axis(
+            Axis::default()
+                .title("s".gray())
+                .bounds([-window, 0.0])
+                .labels([
+                    format!("-{}", window),
+                    format!("-{}", window / 2.0),
+                    "0".to_string(),
+                ]),
+        )
+        .y_axis(Axis::default().bounds([min, max]).labels([
+            format!("{:.2}", min),
+            format!("{:.2}", (min + max) / 2.0),
+            format!("{:.2}", max),
+        ]))
+        .legend_position(Some(LegendPosition::TopLeft))
+        .hidden_legend_constraints((Constraint::Percentage(50), Constraint::Percentage(50)))
+}
+
+fn create_list_series_widget(plot: &Plot) -> Table<'static> {
+    let header = ["", "Series", "Id", "Last"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .bold()
+        .height(1);
+    let rows = plot.series.iter().enumerate().map(|(index, series)| {
+        let last = series.points.back().map_or(String::new(), |(_, value)| {
+            let value = format_value(*value);
+            match &series.units {
+                Some(units) => format!("{} {}", value, units),
+                None => value,
+            }
+        });
+        Row::new([
+            Cell::from("■").style(Style::default().fg(series_color(index))),
+            Cell::from(series.name()),
+            Cell::from(format!("{}:{}", series.system_id, series.component_id)),
+            Cell::from(last),
+        ])
+    });
+    Table::new(
+        rows,
+        [
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(7),
+            Constraint::Length(12),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(Style::default().bg(Color::Blue))
+}
+
+fn create_list_watch_widget(watch: &WatchList, now: DateTime<Local>) -> Table<'static> {
+    let header = [
+        "Field", "Id", "Current", "Min", "Max", "Mean", "Std dev", "Rate/s", "Units",
+    ]
+    .into_iter()
+    .map(Cell::from)
+    .collect::<Row>()
+    .bold()
+    .height(1);
+    let window = watch.window();
+    let rows = watch.fields.iter().map(|field| {
+        let statistics = field.window_statistics(window, now);
+        let [min, max, mean, std_dev] = if statistics.count == 0 {
+            Default::default()
+        } else {
+            [
+                statistics.min,
+                statistics.max,
+                statistics.mean,
+                statistics.std_dev(),
+            ]
+            .map(format_value)
+        };
+        Row::new([
+            field.series.name(),
+            format!("{}:{}", field.series.system_id, field.series.component_id),
+            field.current().map(format_value).unwrap_or_default(),
+            min,
+            max,
+            mean,
+            std_dev,
+            field
+                .rate_of_change(window, now)
+                .map(format_value)
+                .unwrap_or_default(),
+            field.series.units.clone().unwrap_or_default(),
+        ])
+    });
+    Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(7),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(8),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(Style::default().bg(Color::Blue))
+}
+
+fn create_list_streams_widget(
+    stream_rates: &[&StreamRate],
+    now: DateTime<Local>,
+) -> Table<'static> {
+    let header = ["Id", "Message", "Desired, Hz", "Measured, Hz"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .height(1);
+    let rows = stream_rates.iter().map(|r| {
+        let measured_hz = r.measured_hz(now);
+        let measured = Text::from(format!("{:.1}", measured_hz));
+        let measured = match r.desired_hz {
+            _ if measured_hz == 0.0 => measured.dark_gray(),
+            // tolerate the jitter of a 5 second window
+            Some(desired_hz) if (measured_hz - desired_hz).abs() > desired_hz * 0.2 + 0.2 => {
+                measured.yellow()
+            }
+            Some(_) => measured.green(),
+            None => measured,
+        };
+        Row::new(vec![
+            Cell::from(Text::from(r.message_id.to_string()).magenta()),
+            Cell::from(Text::from(r.message_name.clone())),
+            Cell::from(Text::from(match r.desired_hz {
+                None => "default".to_string(),
+                Some(hz) if hz <= 0.0 => "off".to_string(),
+                Some(hz) => format!("{:.1}", hz),
+            })),
+            Cell::from(measured),
+        ])
+    });
+    Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(32),
+            Constraint::Length(14),
+            Constraint::Length(14),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(Style::default().bg(Color::Blue))
+}
+
+/// Messages with their index among messages of every link, and the link when there are several
+fn create_list_events_widget<M: DialectMessage>(
+    messages: &[(usize, &ReceivedFrame<M>)],
+    is_multilink: bool,
+) -> Table<'static> {
+    let rows = messages.iter().map(|(i, f)| {
+        let mut spans = vec![Span::from(format!("{:>4}  ", i)).style(Color::Magenta)];
+        if is_multilink {
+            spans.push(Span::from(format!("[{}] ", f.link_id)).cyan());
+        }
+        spans.push(match &f.content {
+            FrameContent::Message { message, .. } => Span::from(message.message_name().to_string()),
+            FrameContent::Undecodable(reason) => Span::from(format!("?? {}", reason)).red(),
+        });
+        if f.signature.is_rejected() {
+            spans.push(Span::from(format!(" ({})", f.signature)).red());
+        }
+        let cell = Cell::default().content(Line::from(spans));
+        Row::new(vec![cell])
+    });
+
+    Table::new(rows, [Constraint::Fill(1)]).row_highlight_style(Style::default().bg(Color::Blue))
+}
+
+fn create_list_status_texts_widget(status_texts: &[&StatusTextEntry]) -> Table<'static> {
+    let header = ["Time", "Source", "Severity", "Text"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .height(1);
+    let rows = status_texts.iter().map(|e| {
+        let color = severity_color(e.severity);
+        let mut text = vec![Span::from(e.text.clone()).style(color)];
+        if !e.is_complete {
+            text.push(Span::from(" (incomplete)").gray());
+        }
+        Row::new(vec![
+            Cell::from(Text::from(e.received_at.format("%H:%M:%S%.3f").to_string()).gray()),
+            Cell::from(Text::from(format!("{}:{}", e.system_id, e.component_id)).magenta()),
+            Cell::from(Text::from(severity_name(e.severity)).style(color)),
+            Cell::from(Line::from(text)),
+        ])
+    });
+    Table::new(
+        rows,
+        [
+            Constraint::Length(13),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(Style::default().bg(Color::Blue))
+}
+
+/// Active alerts shown above every screen
+fn create_alert_banner(alerts: &Alerts) -> Option<Line<'static>> {
+    let active = alerts.active().collect::<Vec<_>>();
+    let latest = active.iter().max_by_key(|r| r.active_since)?;
+    let mut text = format!(
+        " ⚠ {} ({}) ",
+        latest.text,
+        latest.detail.clone().unwrap_or_default()
+    );
+    if active.len() > 1 {
+        text.push_str(&format!("+{} more ", active.len() - 1));
+    }
+    Some(Line::from(text).white().on_red().bold())
+}
+
+fn create_list_rules_widget(alerts: &Alerts) -> Table<'static> {
+    let header = ["Rule", "State", "Detail"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .height(1);
+    let rows = alerts.rules.iter().map(|rule| {
+        let state = match rule.active_since {
+            Some(since) => Text::from(format!("ALERT since {}", since.format("%H:%M:%S"))).red(),
+            None => Text::from("ok").green(),
+        };
+        Row::new(vec![
+            Cell::from(rule.text.clone()),
+            Cell::from(state),
+            Cell::from(Text::from(rule.detail.clone().unwrap_or_default()).gray()),
+        ])
+    });
+    Table::new(
+        rows,
+        [
+            Constraint::Percentage(40),
+            Constraint::Length(22),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(header)
+}
+
+fn create_list_alert_history_widget(history: &[Alert]) -> Table<'static> {
+    let header = ["Time", "Rule", "Detail"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .height(1);
+    let rows = history.iter().map(|alert| {
+        Row::new(vec![
+            Cell::from(Text::from(alert.time.format("%H:%M:%S%.3f").to_string()).gray()),
+            Cell::from(Text::from(alert.rule.clone()).red()),
+            Cell::from(alert.detail.clone()),
+        ])
+    });
+    Table::new(
+        rows,
+        [
+            Constraint::Length(13),
+            Constraint::Percentage(40),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(Style::default().bg(Color::Blue))
+}
+
+fn create_list_sensors_widget(sensors: &[SensorHealth]) -> Table<'static> {
+    let header = ["Sensor", "Present", "Enabled", "Healthy"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .height(1);
+    let flag_cell = |value: bool, present: bool| {
+        let text = Text::from(if value { "yes" } else { "no" });
+        Cell::from(if !present {
+            text.dark_gray()
+        } else if value {
+            text.green()
+        } else {
+            text.red()
+        })
+    };
+    let rows = sensors.iter().map(|s| {
+        let name = Text::from(s.name.clone());
+        Row::new(vec![
+            Cell::from(if s.is_failing() {
+                name.red().bold()
+            } else if s.present {
+                name
+            } else {
+                name.dark_gray()
+            }),
+            flag_cell(s.present, s.present),
+            flag_cell(s.enabled, s.present),
+            flag_cell(s.healthy, s.present && s.enabled),
+        ])
+    });
+    Table::new(
+        rows,
+        [
+            Constraint::Length(28),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .header(header)
+    .row_highlight_style(Style::default().bg(Color::Blue))
+}
+
+fn severity_color(severity: MavSeverity) -> Style {
+    match severity {
+        MavSeverity::MAV_SEVERITY_EMERGENCY
+        | MavSeverity::MAV_SEVERITY_ALERT
+        | MavSeverity::MAV_SEVERITY_CRITICAL => Style::default().fg(Color::Red).bold(),
+        MavSeverity::MAV_SEVERITY_ERROR => Style::default().fg(Color::Red),
+        MavSeverity::MAV_SEVERITY_WARNING => Style::default().fg(Color::Yellow),
+        MavSeverity::MAV_SEVERITY_NOTICE => Style::default().fg(Color::Cyan),
+        MavSeverity::MAV_SEVERITY_INFO => Style::default(),
+        MavSeverity::MAV_SEVERITY_DEBUG => Style::default().fg(Color::Gray),
+    }
 }
 
 fn create_list_parameters_widget(parameter_messages: &[PARAM_VALUE_DATA]) -> Table<'static> {
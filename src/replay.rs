@@ -0,0 +1,225 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::TimeDelta;
+
+use crate::AppEvent;
+use crate::dialect::DialectMessage;
+use crate::frame::ReceivedFrame;
+
+/// Longest sleep of the replay thread, so controls take effect quickly
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+const MIN_SPEED: f64 = 1.0 / 16.0;
+const MAX_SPEED: f64 = 64.0;
+
+/// Playback position and controls shared between the UI and the replay thread
+struct ReplayState {
+    /// index of the next frame to send
+    position: usize,
+    current_time: DateTime<Local>,
+    speed: f64,
+    is_paused: bool,
+    steps: usize,
+    seek_to: Option<DateTime<Local>>,
+    /// wall clock instant matching a log time, playback is timed from it
+    anchor: Option<(Instant, DateTime<Local>)>,
+}
+
+pub struct Replay {
+    state: Arc<Mutex<ReplayState>>,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub frames: usize,
+}
+
+impl Replay {
+    /// Feeds recorded frames to the app as if they were received now
+    pub fn start<M: DialectMessage>(
+        frames: Vec<ReceivedFrame<M>>,
+        tx: mpsc::Sender<AppEvent<M>>,
+    ) -> Replay {
+        let start = frames.first().map_or_else(Local::now, |f| f.received);
+        let end = frames.last().map_or(start, |f| f.received);
+        let state = Arc::new(Mutex::new(ReplayState {
+            position: 0,
+            current_time: start,
+            speed: 1.0,
+            is_paused: false,
+            steps: 0,
+            seek_to: None,
+            anchor: None,
+        }));
+        let replay = Replay {
+            state: state.clone(),
+            start,
+            end,
+            frames: frames.len(),
+        };
+        thread::spawn(move || play(frames, state, tx));
+        replay
+    }
+
+    pub fn toggle_pause(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.is_paused = !state.is_paused;
+        state.anchor = None;
+    }
+
+    /// Pauses and sends the next frame
+    pub fn step(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.is_paused = true;
+        state.steps += 1;
+    }
+
+    pub fn change_speed(&self, is_faster: bool) {
+        let mut state = self.state.lock().unwrap();
+        let speed = if is_faster {
+            state.speed * 2.0
+        } else {
+            state.speed / 2.0
+        };
+        state.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+        state.anchor = None;
+    }
+
+    pub fn seek(&self, delta: TimeDelta) {
+        let mut state = self.state.lock().unwrap();
+        let from = state.seek_to.unwrap_or(state.current_time);
+        state.seek_to = Some((from + delta).clamp(self.start, self.end));
+    }
+
+    pub fn current_time(&self) -> DateTime<Local> {
+        self.state.lock().unwrap().current_time
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.state.lock().unwrap().speed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().is_paused
+    }
+
+    pub fn position(&self) -> usize {
+        self.state.lock().unwrap().position
+    }
+}
+
+fn play<M: DialectMessage>(
+    frames: Vec<ReceivedFrame<M>>,
+    state: Arc<Mutex<ReplayState>>,
+    tx: mpsc::Sender<AppEvent<M>>,
+) {
+    let send =
+        |frame: &ReceivedFrame<M>| tx.send(AppEvent::Mavlink(Box::new(frame.clone()))).is_ok();
+    loop {
+        let mut state = state.lock().unwrap();
+        if let Some(seek_to) = state.seek_to.take() {
+            let target = frames.partition_point(|f| f.received <= seek_to);
+            // going back rebuilds everything from the first frame
+            if target < state.position {
+                if tx.send(AppEvent::ReplayRestart).is_err() {
+                    return;
+                }
+                state.position = 0;
+            }
+            for frame in &frames[state.position..target] {
+                if !send(frame) {
+                    return;
+                }
+            }
+            state.position = target;
+            state.current_time = seek_to;
+            state.anchor = None;
+            continue;
+        }
+        if state.position >= frames.len() {
+            state.is_paused = true;
+            state.steps = 0;
+        }
+        if state.is_paused && state.steps == 0 {
+            drop(state);
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        }
+
+        let frame = &frames[state.position];
+        if state.steps > 0 {
+            state.steps -= 1;
+        } else {
+            let current_time = state.current_time;
+            let (anchor_instant, anchor_time) =
+                *state.anchor.get_or_insert((Instant::now(), current_time));
+            let log_delay = (frame.received - anchor_time).to_std().unwrap_or_default();
+            let due = anchor_instant + log_delay.div_f64(state.speed);
+            let now = Instant::now();
+            if due > now {
+                drop(state);
+                thread::sleep((due - now).min(POLL_INTERVAL));
+                continue;
+            }
+        }
+        state.position += 1;
+        state.current_time = frame.received;
+        drop(state);
+        if !send(frame) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use chrono::Local;
+    use chrono::TimeDelta;
+    use chrono::TimeZone;
+    use mavlink::common::MavMessage;
+
+    use crate::AppEvent;
+    use crate::dialect::ExtendedMessage;
+    use crate::frame::ReceivedFrame;
+    use crate::replay::Replay;
+
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_replay_seek() {
+        let start = Local.timestamp_opt(1_700_000_000, 0).unwrap();
+        let frames = (0..3)
+            .map(|i| ReceivedFrame::truncated(vec![0xFD], start + TimeDelta::seconds(10 * i)))
+            .collect();
+        let (tx, rx) = mpsc::channel::<AppEvent<ExtendedMessage<MavMessage>>>();
+        let replay = Replay::start(frames, tx);
+        let received = |rx: &mpsc::Receiver<_>| match rx.recv_timeout(TIMEOUT) {
+            Ok(AppEvent::Mavlink(frame)) => Some(frame.received),
+            _ => None,
+        };
+        assert_eq!(received(&rx), Some(start));
+
+        replay.toggle_pause();
+        replay.seek(TimeDelta::seconds(15));
+        assert_eq!(received(&rx), Some(start + TimeDelta::seconds(10)));
+
+        replay.seek(TimeDelta::seconds(-15));
+        assert!(matches!(
+            rx.recv_timeout(TIMEOUT),
+            Ok(AppEvent::ReplayRestart)
+        ));
+        assert_eq!(received(&rx), Some(start));
+        assert_eq!(replay.position(), 1);
+
+        replay.step();
+        assert_eq!(received(&rx), Some(start + TimeDelta::seconds(10)));
+        assert!(replay.is_paused());
+    }
+}
@@ -0,0 +1,720 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+
+use crate::message_fields::FieldValue;
+use crate::message_fields::MessageField;
+
+/// Definitions loaded with --dialect-xml, shared by the receive thread and the UI
+static RUNTIME_DIALECT: OnceLock<RuntimeDialect> = OnceLock::new();
+
+/// Definitions of the --dialect message set, for units and enum descriptions
+static STANDARD_DIALECT: OnceLock<RuntimeDialect> = OnceLock::new();
+
+macro_rules! standard_definitions {
+    ($($file_name:literal),* $(,)?) => {
+        [$(($file_name, include_str!(concat!("../message_definitions/v1.0/", $file_name)))),*]
+    };
+}
+
+const STANDARD_DEFINITIONS: [(&str, &str); 15] = standard_definitions!(
+    "ASLUAV.xml",
+    "AVSSUAS.xml",
+    "all.xml",
+    "ardupilotmega.xml",
+    "common.xml",
+    "csAirLink.xml",
+    "cubepilot.xml",
+    "development.xml",
+    "icarous.xml",
+    "loweheiser.xml",
+    "minimal.xml",
+    "standard.xml",
+    "storm32.xml",
+    "uAvionix.xml",
+    "ualberta.xml",
+);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float,
+    Double,
+    Char,
+}
+
+impl FieldType {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "int8_t" => Some(FieldType::Int8),
+            "uint8_t" | "uint8_t_mavlink_version" => Some(FieldType::UInt8),
+            "int16_t" => Some(FieldType::Int16),
+            "uint16_t" => Some(FieldType::UInt16),
+            "int32_t" => Some(FieldType::Int32),
+            "uint32_t" => Some(FieldType::UInt32),
+            "int64_t" => Some(FieldType::Int64),
+            "uint64_t" => Some(FieldType::UInt64),
+            "float" => Some(FieldType::Float),
+            "double" => Some(FieldType::Double),
+            "char" => Some(FieldType::Char),
+            _ => None,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            FieldType::Int8 | FieldType::UInt8 | FieldType::Char => 1,
+            FieldType::Int16 | FieldType::UInt16 => 2,
+            FieldType::Int32 | FieldType::UInt32 | FieldType::Float => 4,
+            FieldType::Int64 | FieldType::UInt64 | FieldType::Double => 8,
+        }
+    }
+
+    /// Type name as used in the CRC_EXTRA calculation
+    pub fn c_name(&self) -> &'static str {
+        match self {
+            FieldType::Int8 => "int8_t",
+            FieldType::UInt8 => "uint8_t",
+            FieldType::Int16 => "int16_t",
+            FieldType::UInt16 => "uint16_t",
+            FieldType::Int32 => "int32_t",
+            FieldType::UInt32 => "uint32_t",
+            FieldType::Int64 => "int64_t",
+            FieldType::UInt64 => "uint64_t",
+            FieldType::Float => "float",
+            FieldType::Double => "double",
+            FieldType::Char => "char",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FieldDefinition {
+    pub name: String,
+    pub field_type: FieldType,
+    pub array_len: Option<usize>,
+    pub enum_name: Option<String>,
+    pub units: Option<String>,
+    pub is_bitmask: bool,
+    pub is_extension: bool,
+    /// Position of the field as declared, fields are shown in this order
+    pub declaration_index: usize,
+}
+
+impl FieldDefinition {
+    pub fn size(&self) -> usize {
+        self.field_type.size() * self.array_len.unwrap_or(1)
+    }
+}
+
+#[derive(Debug)]
+pub struct MessageDefinition {
+    pub id: u32,
+    pub name: String,
+    /// fields in wire order: largest types first, then extensions as declared
+    pub fields: Vec<FieldDefinition>,
+    pub extra_crc: u8,
+}
+
+impl MessageDefinition {
+    /// Payload length with all extension fields
+    pub fn encoded_len(&self) -> usize {
+        self.fields.iter().map(|f| f.size()).sum()
+    }
+}
+
+#[derive(Debug)]
+pub struct EnumEntry {
+    pub value: u64,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct EnumDefinition {
+    pub is_bitmask: bool,
+    pub entries: Vec<EnumEntry>,
+}
+
+#[derive(Debug, Default)]
+pub struct RuntimeDialect {
+    messages: BTreeMap<u32, MessageDefinition>,
+    enums: HashMap<String, EnumDefinition>,
+}
+
+impl RuntimeDialect {
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut dialect = RuntimeDialect::default();
+        dialect.load_file(path, &mut HashSet::new())?;
+        Ok(dialect)
+    }
+
+    /// Definitions the mavlink crate was generated from, built into the binary
+    pub fn standard(file_name: &str) -> Result<Self> {
+        let mut dialect = RuntimeDialect::default();
+        dialect.load_standard_file(file_name, &mut HashSet::new())?;
+        Ok(dialect)
+    }
+
+    fn load_file(&mut self, path: &Path, visited: &mut HashSet<PathBuf>) -> Result<()> {
+        let path = path
+            .canonicalize()
+            .map_err(|e| eyre!("Could not open {}: {}", path.display(), e))?;
+        if !visited.insert(path.clone()) {
+            return Ok(());
+        }
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| eyre!("Could not read {}: {}", path.display(), e))?;
+        let parent = path.parent().unwrap_or(Path::new("."));
+        self.load_text(&text, &path.display().to_string(), |dialect, include| {
+            dialect.load_file(&parent.join(include), visited)
+        })
+    }
+
+    fn load_standard_file(
+        &mut self,
+        file_name: &str,
+        visited: &mut HashSet<PathBuf>,
+    ) -> Result<()> {
+        if !visited.insert(PathBuf::from(file_name)) {
+            return Ok(());
+        }
+        let (_, text) = STANDARD_DEFINITIONS
+            .iter()
+            .find(|(name, _)| *name == file_name)
+            .ok_or(eyre!("No built-in definitions {}", file_name))?;
+        self.load_text(text, file_name, |dialect, include| {
+            dialect.load_standard_file(include, visited)
+        })
+    }
+
+    /// Loads includes first, so a file can override what it includes
+    fn load_text(
+        &mut self,
+        text: &str,
+        source: &str,
+        mut load_include: impl FnMut(&mut Self, &str) -> Result<()>,
+    ) -> Result<()> {
+        let document = roxmltree::Document::parse(text)
+            .map_err(|e| eyre!("Invalid XML in {}: {}", source, e))?;
+        let root = document.root_element();
+
+        for include in root.children().filter(|n| n.has_tag_name("include")) {
+            load_include(self, include.text().unwrap_or_default().trim())?;
+        }
+        for node in root.descendants().filter(|n| n.has_tag_name("enum")) {
+            self.add_enum(node);
+        }
+        for node in root.descendants().filter(|n| n.has_tag_name("message")) {
+            let message =
+                parse_message(node).map_err(|e| eyre!("Invalid message in {}: {}", source, e))?;
+            self.messages.insert(message.id, message);
+        }
+        Ok(())
+    }
+
+    fn add_enum(&mut self, node: roxmltree::Node) {
+        let Some(name) = node.attribute("name") else {
+            return;
+        };
+        let definition = self.enums.entry(name.to_string()).or_default();
+        definition.is_bitmask |= node.attribute("bitmask") == Some("true");
+        let mut next_value = 0;
+        for entry in node.children().filter(|n| n.has_tag_name("entry")) {
+            // entries without a value continue the numbering of the previous one
+            let value = entry
+                .attribute("value")
+                .and_then(parse_number)
+                .unwrap_or(next_value);
+            next_value = value + 1;
+            if let Some(entry_name) = entry.attribute("name") {
+                definition.entries.push(EnumEntry {
+                    value,
+                    name: entry_name.to_string(),
+                    description: entry
+                        .children()
+                        .find(|n| n.has_tag_name("description"))
+                        .and_then(|n| n.text())
+                        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+                        .filter(|text| !text.is_empty()),
+                });
+            }
+        }
+    }
+
+    pub fn message(&self, id: u32) -> Option<&MessageDefinition> {
+        self.messages.get(&id)
+    }
+
+    pub fn message_id_from_name(&self, name: &str) -> Option<u32> {
+        self.messages
+            .values()
+            .find(|m| m.name == name)
+            .map(|m| m.id)
+    }
+
+    /// Decoded fields in declaration order, missing trailing bytes are zeros as on the wire
+    pub fn decode(&self, message: &MessageDefinition, payload: &[u8]) -> Vec<MessageField> {
+        let mut bytes = payload.to_vec();
+        bytes.resize(bytes.len().max(message.encoded_len()), 0);
+        let mut offset = 0;
+        let mut fields = message
+            .fields
+            .iter()
+            .map(|field| {
+                let field_bytes = &bytes[offset..offset + field.size()];
+                offset += field.size();
+                (
+                    field.declaration_index,
+                    self.decode_field(field, field_bytes),
+                )
+            })
+            .collect::<Vec<_>>();
+        fields.sort_by_key(|(index, _)| *index);
+        fields.into_iter().map(|(_, field)| field).collect()
+    }
+
+    fn decode_field(&self, field: &FieldDefinition, bytes: &[u8]) -> MessageField {
+        let value = if field.field_type == FieldType::Char && field.array_len.is_some() {
+            FieldValue::Text(
+                bytes
+                    .iter()
+                    .take_while(|b| **b != 0)
+                    .map(|b| *b as char)
+                    .collect(),
+            )
+        } else {
+            let mut values = bytes
+                .chunks(field.field_type.size())
+                .map(|chunk| self.decode_value(field, read_value(field.field_type, chunk)))
+                .collect::<Vec<_>>();
+            match field.array_len {
+                Some(_) => FieldValue::Array(values),
+                None => values.remove(0),
+            }
+        };
+        let type_name = match (&field.enum_name, field.array_len) {
+            (Some(enum_name), _) => enum_name.clone(),
+            (None, Some(len)) => format!("{}[{}]", field.field_type.c_name(), len),
+            (None, None) => field.field_type.c_name().to_string(),
+        };
+        MessageField {
+            name: field.name.clone(),
+            type_name,
+            description: self.describe(field, &value),
+            value,
+            units: field.units.clone(),
+        }
+    }
+
+    /// Adds units, enum names and descriptions to fields of a compiled message and puts them
+    /// in declaration order; fields matched by name survive definitions of another version
+    pub fn annotate(&self, message_id: u32, fields: Vec<MessageField>) -> Vec<MessageField> {
+        let Some(message) = self.message(message_id) else {
+            return fields;
+        };
+        let mut fields = fields
+            .into_iter()
+            .map(|mut field| {
+                // the mavlink crate renames "type", a Rust keyword
+                let name = match field.name.as_str() {
+                    "mavtype" => "type",
+                    name => name,
+                };
+                let Some(definition) = message.fields.iter().find(|d| d.name == name) else {
+                    // unknown to the definitions, kept after the known fields
+                    return (usize::MAX, field);
+                };
+                field.units = definition.units.clone();
+                field.value = self.decode_value(definition, field.value.clone());
+                field.description = self.describe(definition, &field.value);
+                (definition.declaration_index, field)
+            })
+            .collect::<Vec<_>>();
+        fields.sort_by_key(|(index, _)| *index);
+        fields.into_iter().map(|(_, field)| field).collect()
+    }
+
+    /// Value of an enum entry, ex. MAV_CMD_COMPONENT_ARM_DISARM of MAV_CMD
+    pub fn enum_value(&self, enum_name: &str, entry_name: &str) -> Option<u64> {
+        self.enums
+            .get(enum_name)?
+            .entries
+            .iter()
+            .find(|entry| entry.name == entry_name)
+            .map(|entry| entry.value)
+    }
+
+    fn describe(&self, field: &FieldDefinition, value: &FieldValue) -> Option<String> {
+        let FieldValue::Enum(name) = value else {
+            return None;
+        };
+        self.enums
+            .get(field.enum_name.as_ref()?)?
+            .entries
+            .iter()
+            .find(|entry| entry.name == *name)?
+            .description
+            .clone()
+    }
+
+    fn decode_value(&self, field: &FieldDefinition, value: FieldValue) -> FieldValue {
+        // compiled messages hold strings as byte arrays
+        if let FieldValue::Array(values) = &value
+            && field.field_type == FieldType::Char
+        {
+            return FieldValue::Text(
+                values
+                    .iter()
+                    .map_while(|v| match v {
+                        FieldValue::Unsigned(b) if *b != 0 => Some(*b as u8 as char),
+                        _ => None,
+                    })
+                    .collect(),
+            );
+        }
+        let number = match value {
+            FieldValue::Signed(v) => v as u64,
+            FieldValue::Unsigned(v) => v,
+            _ => return value,
+        };
+        let Some(definition) = field.enum_name.as_ref().and_then(|n| self.enums.get(n)) else {
+            return value;
+        };
+        if field.is_bitmask || definition.is_bitmask {
+            let flags = definition
+                .entries
+                .iter()
+                .filter(|entry| entry.value != 0 && number & entry.value == entry.value)
+                .collect::<Vec<_>>();
+            let known_bits = flags.iter().fold(0, |bits, entry| bits | entry.value);
+            let mut names = flags
+                .into_iter()
+                .map(|entry| entry.name.clone())
+                .collect::<Vec<_>>();
+            if number & !known_bits != 0 {
+                names.push(format!("0x{:x}", number & !known_bits));
+            }
+            FieldValue::Flags(names)
+        } else {
+            definition
+                .entries
+                .iter()
+                .find(|entry| entry.value == number)
+                .map_or(value, |entry| FieldValue::Enum(entry.name.clone()))
+        }
+    }
+}
+
+fn parse_message(node: roxmltree::Node) -> Result<MessageDefinition, String> {
+    let name = node.attribute("name").ok_or("message without a name")?;
+    let id = node
+        .attribute("id")
+        .and_then(|id| id.parse::<u32>().ok())
+        .ok_or(format!("{} has no valid id", name))?;
+    let mut fields = vec![];
+    let mut is_extension = false;
+    for child in node.children().filter(|n| n.is_element()) {
+        if child.has_tag_name("extensions") {
+            is_extension = true;
+        }
+        if !child.has_tag_name("field") {
+            continue;
+        }
+        let field_name = child
+            .attribute("name")
+            .ok_or(format!("{} has a field without a name", name))?;
+        let type_name = child.attribute("type").unwrap_or_default();
+        let (base_type, array_len) = match type_name.split_once('[') {
+            Some((base, len)) => (base, len.trim_end_matches(']').parse::<usize>().ok()),
+            None => (type_name, None),
+        };
+        let field_type = FieldType::parse(base_type).ok_or(format!(
+            "{}.{} has unknown type {}",
+            name, field_name, type_name
+        ))?;
+        fields.push(FieldDefinition {
+            name: field_name.to_string(),
+            field_type,
+            array_len,
+            enum_name: child.attribute("enum").map(str::to_string),
+            units: child.attribute("units").map(str::to_string),
+            is_bitmask: child.attribute("display") == Some("bitmask"),
+            is_extension,
+            declaration_index: fields.len(),
+        });
+    }
+    // stable sort keeps the declaration order among fields of the same size
+    let extensions_start = fields
+        .iter()
+        .position(|f| f.is_extension)
+        .unwrap_or(fields.len());
+    fields[..extensions_start].sort_by_key(|f| std::cmp::Reverse(f.field_type.size()));
+    let extra_crc = calculate_extra_crc(name, &fields[..extensions_start]);
+    Ok(MessageDefinition {
+        id,
+        name: name.to_string(),
+        fields,
+        extra_crc,
+    })
+}
+
+fn calculate_extra_crc(name: &str, fields: &[FieldDefinition]) -> u8 {
+    let mut crc = X25::default();
+    crc.accumulate(format!("{} ", name).as_bytes());
+    for field in fields {
+        crc.accumulate(format!("{} ", field.field_type.c_name()).as_bytes());
+        crc.accumulate(format!("{} ", field.name).as_bytes());
+        if let Some(len) = field.array_len {
+            crc.accumulate(&[len as u8]);
+        }
+    }
+    ((crc.0 & 0xFF) ^ (crc.0 >> 8)) as u8
+}
+
+/// CRC-16/MCRF4XX used by MAVLink
+struct X25(u16);
+
+impl Default for X25 {
+    fn default() -> Self {
+        X25(0xFFFF)
+    }
+}
+
+impl X25 {
+    fn accumulate(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            let mut tmp = *byte ^ (self.0 & 0xFF) as u8;
+            tmp ^= tmp << 4;
+            let tmp = tmp as u16;
+            self.0 = (self.0 >> 8) ^ (tmp << 8) ^ (tmp << 3) ^ (tmp >> 4);
+        }
+    }
+}
+
+fn read_value(field_type: FieldType, bytes: &[u8]) -> FieldValue {
+    let mut buffer = [0u8; 8];
+    buffer[..bytes.len()].copy_from_slice(bytes);
+    match field_type {
+        FieldType::Int8 => FieldValue::Signed(bytes[0] as i8 as i64),
+        FieldType::UInt8 | FieldType::Char => FieldValue::Unsigned(bytes[0] as u64),
+        FieldType::Int16 => FieldValue::Signed(i16::from_le_bytes([bytes[0], bytes[1]]) as i64),
+        FieldType::UInt16 => FieldValue::Unsigned(u16::from_le_bytes([bytes[0], bytes[1]]) as u64),
+        FieldType::Int32 => {
+            FieldValue::Signed(i32::from_le_bytes(buffer[..4].try_into().unwrap()) as i64)
+        }
+        FieldType::UInt32 => {
+            FieldValue::Unsigned(u32::from_le_bytes(buffer[..4].try_into().unwrap()) as u64)
+        }
+        FieldType::Int64 => FieldValue::Signed(i64::from_le_bytes(buffer)),
+        FieldType::UInt64 => FieldValue::Unsigned(u64::from_le_bytes(buffer)),
+        FieldType::Float => {
+            FieldValue::Float(f32::from_le_bytes(buffer[..4].try_into().unwrap()) as f64)
+        }
+        FieldType::Double => FieldValue::Float(f64::from_le_bytes(buffer)),
+    }
+}
+
+fn parse_number(text: &str) -> Option<u64> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => text.parse::<u64>().ok(),
+    }
+}
+
+/// Makes the definitions available to message decoding, only the first call has an effect
+pub fn install(dialect: RuntimeDialect) {
+    let _ = RUNTIME_DIALECT.set(dialect);
+}
+
+pub fn installed() -> Option<&'static RuntimeDialect> {
+    RUNTIME_DIALECT.get()
+}
+
+pub fn install_standard(dialect: RuntimeDialect) {
+    let _ = STANDARD_DIALECT.set(dialect);
+}
+
+pub fn standard() -> Option<&'static RuntimeDialect> {
+    STANDARD_DIALECT.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use mavlink::Message;
+    use mavlink::ardupilotmega::GLOBAL_POSITION_INT_DATA;
+    use mavlink::ardupilotmega::HEARTBEAT_DATA;
+    use mavlink::ardupilotmega::MavMessage;
+    use mavlink::ardupilotmega::PARAM_VALUE_DATA;
+
+    use crate::message_fields::FieldValue;
+    use crate::message_fields::message_fields;
+    use crate::runtime_dialect::RuntimeDialect;
+
+    const BASE_XML: &str = r#"<?xml version="1.0"?>
+<mavlink>
+  <enums>
+    <enum name="MAV_STATE">
+      <entry value="0" name="MAV_STATE_UNINIT"/>
+      <entry value="4" name="MAV_STATE_ACTIVE"/>
+    </enum>
+  </enums>
+  <messages>
+    <message id="0" name="HEARTBEAT">
+      <field type="uint8_t" name="type">Type</field>
+      <field type="uint8_t" name="autopilot">Autopilot</field>
+      <field type="uint8_t" name="base_mode">Mode</field>
+      <field type="uint32_t" name="custom_mode">Custom mode</field>
+      <field type="uint8_t" name="system_status" enum="MAV_STATE">State</field>
+      <field type="uint8_t_mavlink_version" name="mavlink_version">Version</field>
+    </message>
+  </messages>
+</mavlink>"#;
+
+    const PRIVATE_XML: &str = r#"<?xml version="1.0"?>
+<mavlink>
+  <include>base.xml</include>
+  <enums>
+    <enum name="PAYLOAD_FLAGS" bitmask="true">
+      <entry value="1" name="PAYLOAD_ARMED"/>
+      <entry value="0x4" name="PAYLOAD_HOT"/>
+    </enum>
+  </enums>
+  <messages>
+    <message id="42000" name="PAYLOAD_STATE">
+      <field type="uint8_t" name="flags" enum="PAYLOAD_FLAGS">Flags</field>
+      <field type="int16_t" name="temperature" units="cdegC">Temperature</field>
+      <field type="char[8]" name="label">Label</field>
+      <extensions/>
+      <field type="uint8_t" name="state" enum="MAV_STATE">State</field>
+    </message>
+  </messages>
+</mavlink>"#;
+
+    fn load_test_dialect(dir_name: &str) -> RuntimeDialect {
+        let dir = std::env::temp_dir().join(dir_name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("base.xml"), BASE_XML).unwrap();
+        fs::write(dir.join("private.xml"), PRIVATE_XML).unwrap();
+        RuntimeDialect::load(&dir.join("private.xml")).unwrap()
+    }
+
+    #[test]
+    fn test_load_with_includes() {
+        let dialect = load_test_dialect("mavlink_debugger_test_includes");
+        // matches CRC_EXTRA of the standard HEARTBEAT
+        assert_eq!(dialect.message(0).unwrap().extra_crc, 50);
+        let payload_state = dialect.message(42000).unwrap();
+        let names = payload_state
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["temperature", "flags", "label", "state"]);
+        assert_eq!(payload_state.encoded_len(), 12);
+    }
+
+    #[test]
+    fn test_decode() {
+        let dialect = load_test_dialect("mavlink_debugger_test_decode");
+        let payload_state = dialect.message(42000).unwrap();
+        let payload = [0xF6, 0xFF, 5, b'p', b'o', b'd', 0, 0, 0, 0, 0, 4];
+        let fields = dialect
+            .decode(payload_state, &payload)
+            .iter()
+            .map(|f| (f.name.clone(), f.type_name.clone(), f.formatted_value()))
+            .collect::<Vec<_>>();
+        let field = |name: &str, type_name: &str, value: &str| {
+            (name.to_string(), type_name.to_string(), value.to_string())
+        };
+        assert_eq!(
+            fields,
+            vec![
+                field("flags", "PAYLOAD_FLAGS", "PAYLOAD_ARMED | PAYLOAD_HOT"),
+                field("temperature", "int16_t", "-10 cdegC"),
+                field("label", "char[8]", "\"pod\""),
+                field("state", "MAV_STATE", "MAV_STATE_ACTIVE"),
+            ]
+        );
+        // truncated payload, the extension defaults to zero
+        let fields = dialect.decode(payload_state, &payload[..3]);
+        assert_eq!(
+            fields[3].value,
+            FieldValue::Enum("MAV_STATE_UNINIT".to_string())
+        );
+    }
+
+    #[test]
+    fn test_annotate_compiled_message() {
+        let dialect = RuntimeDialect::standard("ardupilotmega.xml").unwrap();
+        let message = MavMessage::GLOBAL_POSITION_INT(GLOBAL_POSITION_INT_DATA {
+            lat: 473977419,
+            ..Default::default()
+        });
+        let fields = dialect.annotate(message.message_id(), message_fields(&message));
+        let lat = fields.iter().find(|f| f.name == "lat").unwrap();
+        assert_eq!(lat.units.as_deref(), Some("degE7"));
+        assert_eq!(lat.scaled_value(), Some("47.3977419°".to_string()));
+
+        let message = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        let fields = dialect.annotate(message.message_id(), message_fields(&message));
+        let autopilot = fields.iter().find(|f| f.name == "autopilot").unwrap();
+        assert_eq!(
+            autopilot.description.as_deref(),
+            Some("Generic autopilot, full support for everything")
+        );
+
+        let mut param_id = [0; 16];
+        param_id[..9].copy_from_slice(b"RATE_ROLL");
+        let message = MavMessage::PARAM_VALUE(PARAM_VALUE_DATA {
+            param_id,
+            ..Default::default()
+        });
+        let fields = dialect.annotate(message.message_id(), message_fields(&message));
+        let param_id = fields.iter().find(|f| f.name == "param_id").unwrap();
+        assert_eq!(param_id.formatted_value(), "\"RATE_ROLL\"");
+    }
+
+    #[test]
+    fn test_annotate_other_definition_version() {
+        let dialect = RuntimeDialect::standard("ardupilotmega.xml").unwrap();
+        let message = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        let mut fields = message_fields(&message);
+        // a field the crate lacks and one the definitions lack
+        fields.retain(|f| f.name != "custom_mode");
+        let mut extra = fields[0].clone();
+        extra.name = "extra".to_string();
+        fields.insert(0, extra);
+
+        let fields = dialect.annotate(message.message_id(), fields);
+        let names = fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "mavtype",
+                "autopilot",
+                "base_mode",
+                "system_status",
+                "mavlink_version",
+                "extra"
+            ]
+        );
+        assert!(fields[0].description.is_some());
+        assert!(fields[1].description.is_some());
+    }
+}
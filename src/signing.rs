@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::DateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use color_eyre::Result;
+use color_eyre::eyre::eyre;
+use sha2::Digest;
+use sha2::Sha256;
+use strum::Display;
+
+use crate::frame::CHECKSUM_LEN;
+use crate::frame::INCOMPAT_FLAG_SIGNED;
+use crate::frame::SIGNATURE_LEN;
+use crate::frame::V2_HEADER_LEN;
+use crate::frame::is_signed;
+use crate::frame::message_id;
+
+const KEY_LEN: usize = 32;
+/// Bytes of the SHA-256 hash kept at the end of a signature
+const HASH_LEN: usize = 6;
+/// Bytes of the timestamp, counted in 10 microseconds since 2015-01-01 00:00:00 UTC
+const TIMESTAMP_LEN: usize = 6;
+/// How far, in timestamp units, a stream heard for the first time may be behind, one minute
+const NEW_STREAM_TOLERANCE: u64 = 6_000_000;
+/// Messages accepted unsigned, RADIO_STATUS that telemetry radios insert as the signing spec allows
+const ACCEPTED_UNSIGNED: [u32; 1] = [109];
+
+/// What the signature of a received frame says about it
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SignatureStatus {
+    #[strum(to_string = "unsigned")]
+    Unsigned,
+    /// Signed, but there is no key to check it or the frame comes from a log
+    #[strum(to_string = "signed, not checked")]
+    Unchecked,
+    #[strum(to_string = "valid signature")]
+    Valid,
+    #[strum(to_string = "invalid signature")]
+    Invalid,
+    /// Valid signature with a timestamp not newer than the last one of its stream
+    #[strum(to_string = "replayed timestamp")]
+    Replayed,
+    /// Unsigned while a key is loaded
+    #[strum(to_string = "missing signature")]
+    Missing,
+}
+
+impl SignatureStatus {
+    /// Status of a frame whose signature is not checked
+    pub fn unchecked(raw: &[u8]) -> Self {
+        if is_signed(raw) {
+            SignatureStatus::Unchecked
+        } else {
+            SignatureStatus::Unsigned
+        }
+    }
+
+    /// Rejected frames are kept in the history but do not change the state
+    pub fn is_rejected(&self) -> bool {
+        matches!(
+            self,
+            SignatureStatus::Invalid | SignatureStatus::Replayed | SignatureStatus::Missing
+        )
+    }
+}
+
+#[derive(Default)]
+struct Timestamps {
+    /// Last timestamp sent or received with a valid signature, sent timestamps always grow
+    local: u64,
+    /// Last timestamp of every (system id, component id, link id) stream
+    streams: HashMap<(u8, u8, u8), u64>,
+}
+
+/// Secret key of MAVLink 2 message signing, shared by the links and the sending threads
+pub struct Signing {
+    key: [u8; KEY_LEN],
+    /// Link id put into signatures of sent messages
+    link_id: u8,
+    timestamps: Mutex<Timestamps>,
+}
+
+impl Signing {
+    pub fn new(key: [u8; KEY_LEN], link_id: u8) -> Self {
+        Signing {
+            key,
+            link_id,
+            timestamps: Mutex::default(),
+        }
+    }
+
+    /// Key from a file of 64 hex digits or 32 bytes, or the SHA-256 of a passphrase
+    /// as QGroundControl and Mission Planner derive it; None without either
+    pub fn load(
+        key_file: Option<&Path>,
+        passphrase: Option<&str>,
+        link_id: u8,
+    ) -> Result<Option<Self>> {
+        let key = match (key_file, passphrase) {
+            (Some(path), _) => {
+                let bytes = std::fs::read(path)
+                    .map_err(|e| eyre!("Could not read {}: {}", path.display(), e))?;
+                parse_key(&bytes).ok_or_else(|| {
+                    eyre!(
+                        "{} holds no signing key, expected 64 hex digits or 32 bytes",
+                        path.display()
+                    )
+                })?
+            }
+            (None, Some(passphrase)) => Sha256::digest(passphrase.as_bytes()).into(),
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(Signing::new(key, link_id)))
+    }
+
+    fn hash(&self, signed_bytes: &[u8]) -> [u8; HASH_LEN] {
+        let digest = Sha256::new()
+            .chain_update(self.key)
+            .chain_update(signed_bytes)
+            .finalize();
+        let mut hash = [0u8; HASH_LEN];
+        hash.copy_from_slice(&digest[..HASH_LEN]);
+        hash
+    }
+
+    /// Checks the signature of a received frame and that its timestamp moves forward
+    pub fn check(&self, raw: &[u8]) -> SignatureStatus {
+        if !is_signed(raw) {
+            let is_accepted = message_id(raw).is_some_and(|id| ACCEPTED_UNSIGNED.contains(&id));
+            return if is_accepted {
+                SignatureStatus::Unsigned
+            } else {
+                SignatureStatus::Missing
+            };
+        }
+        let Some(signature_start) = raw.len().checked_sub(SIGNATURE_LEN) else {
+            return SignatureStatus::Invalid;
+        };
+        if signature_start < V2_HEADER_LEN + CHECKSUM_LEN {
+            return SignatureStatus::Invalid;
+        }
+        let (signed_bytes, hash) = raw.split_at(raw.len() - HASH_LEN);
+        if self.hash(signed_bytes) != hash {
+            return SignatureStatus::Invalid;
+        }
+
+        let link_id = raw[signature_start];
+        let timestamp =
+            read_timestamp(&raw[signature_start + 1..signature_start + 1 + TIMESTAMP_LEN]);
+        let mut timestamps = self.timestamps.lock().unwrap();
+        let local = timestamps.local.max(timestamp_at(Utc::now()));
+        let is_replayed = match timestamps.streams.get(&(raw[5], raw[6], link_id)) {
+            Some(last) => timestamp <= *last,
+            None => timestamp + NEW_STREAM_TOLERANCE < local,
+        };
+        if is_replayed {
+            return SignatureStatus::Replayed;
+        }
+        timestamps
+            .streams
+            .insert((raw[5], raw[6], link_id), timestamp);
+        timestamps.local = timestamps.local.max(timestamp);
+        SignatureStatus::Valid
+    }
+
+    /// Signs a complete unsigned MAVLink 2 frame: sets the signed flag, recomputes the checksum
+    /// with the CRC_EXTRA of the message and appends link id, timestamp and signature
+    pub fn sign(&self, bytes: &mut Vec<u8>, extra_crc: u8) {
+        let payload_end = V2_HEADER_LEN + bytes[1] as usize;
+        bytes.truncate(payload_end);
+        bytes[2] |= INCOMPAT_FLAG_SIGNED;
+        let checksum = mavlink::calculate_crc(&bytes[1..payload_end], extra_crc);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        let timestamp = {
+            let mut timestamps = self.timestamps.lock().unwrap();
+            timestamps.local = (timestamps.local + 1).max(timestamp_at(Utc::now()));
+            timestamps.local
+        };
+        bytes.push(self.link_id);
+        bytes.extend_from_slice(&timestamp.to_le_bytes()[..TIMESTAMP_LEN]);
+        let hash = self.hash(bytes);
+        bytes.extend_from_slice(&hash);
+    }
+}
+
+/// 64 hex digits, surrounding whitespace allowed, or exactly 32 bytes
+fn parse_key(bytes: &[u8]) -> Option<[u8; KEY_LEN]> {
+    let text = std::str::from_utf8(bytes)
+        .map(str::trim)
+        .unwrap_or_default();
+    if text.len() == KEY_LEN * 2 && text.chars().all(|c| c.is_ascii_hexdigit()) {
+        let mut key = [0u8; KEY_LEN];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&text[i * 2..i * 2 + 2], 16).ok()?;
+        }
+        return Some(key);
+    }
+    bytes.try_into().ok()
+}
+
+/// Signature timestamp of a time, in 10 microseconds since 2015-01-01 00:00:00 UTC
+pub fn timestamp_at(time: DateTime<Utc>) -> u64 {
+    let epoch = Utc.with_ymd_and_hms(2015, 1, 1, 0, 0, 0).unwrap();
+    (time - epoch).num_microseconds().unwrap_or_default().max(0) as u64 / 10
+}
+
+fn read_timestamp(bytes: &[u8]) -> u64 {
+    let mut timestamp = [0u8; 8];
+    timestamp[..TIMESTAMP_LEN].copy_from_slice(bytes);
+    u64::from_le_bytes(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use chrono::TimeZone;
+    use chrono::Utc;
+    use mavlink::MavHeader;
+    use mavlink::MavlinkVersion;
+    use mavlink::Message;
+    use mavlink::common::HEARTBEAT_DATA;
+    use mavlink::common::MavMessage;
+    use mavlink::common::RADIO_STATUS_DATA;
+
+    use crate::frame::FrameContent;
+    use crate::frame::decode_frame;
+    use crate::signing::SignatureStatus;
+    use crate::signing::Signing;
+    use crate::signing::parse_key;
+    use crate::signing::timestamp_at;
+
+    fn signed_heartbeat(signing: &Signing, system_id: u8) -> Vec<u8> {
+        let header = MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: 0,
+        };
+        let message = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        let mut bytes = vec![];
+        mavlink::write_versioned_msg(&mut bytes, MavlinkVersion::V2, header, &message).unwrap();
+        signing.sign(&mut bytes, MavMessage::extra_crc(message.message_id()));
+        bytes
+    }
+
+    #[test]
+    fn test_sign_and_check() {
+        let sender = Signing::new([7; 32], 2);
+        let receiver = Signing::new([7; 32], 0);
+        let first = signed_heartbeat(&sender, 1);
+        let second = signed_heartbeat(&sender, 1);
+
+        let frame = decode_frame::<MavMessage>(first.clone(), Local::now());
+        assert!(matches!(frame.content, FrameContent::Message { .. }));
+        assert_eq!(frame.signature, SignatureStatus::Unchecked);
+        assert_eq!(first[first.len() - 13], 2);
+
+        assert_eq!(receiver.check(&first), SignatureStatus::Valid);
+        assert_eq!(receiver.check(&second), SignatureStatus::Valid);
+        assert_eq!(receiver.check(&first), SignatureStatus::Replayed);
+        // streams of other systems keep their own timestamps
+        assert_eq!(
+            receiver.check(&signed_heartbeat(&sender, 2)),
+            SignatureStatus::Valid
+        );
+
+        let mut tampered = signed_heartbeat(&sender, 1);
+        tampered[12] ^= 1;
+        assert_eq!(receiver.check(&tampered), SignatureStatus::Invalid);
+        let other_key = Signing::new([8; 32], 0);
+        assert_eq!(other_key.check(&second), SignatureStatus::Invalid);
+
+        let mut unsigned = vec![];
+        let header = MavHeader::default();
+        let message = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        mavlink::write_versioned_msg(&mut unsigned, MavlinkVersion::V2, header, &message).unwrap();
+        assert_eq!(receiver.check(&unsigned), SignatureStatus::Missing);
+        assert!(SignatureStatus::Missing.is_rejected());
+        // telemetry radios do not sign their RADIO_STATUS
+        let mut radio_status = vec![];
+        let message = MavMessage::RADIO_STATUS(RADIO_STATUS_DATA::default());
+        mavlink::write_versioned_msg(&mut radio_status, MavlinkVersion::V2, header, &message)
+            .unwrap();
+        assert_eq!(receiver.check(&radio_status), SignatureStatus::Unsigned);
+    }
+
+    #[test]
+    fn test_parse_key() {
+        let hex = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\n";
+        let key = parse_key(hex.as_bytes()).unwrap();
+        assert_eq!(key[..3], [0, 1, 2]);
+        assert_eq!(key[31], 31);
+        assert_eq!(parse_key(&[5; 32]), Some([5; 32]));
+        assert_eq!(parse_key(b"not a key"), None);
+
+        let epoch = Utc.with_ymd_and_hms(2015, 1, 1, 0, 0, 1).unwrap();
+        assert_eq!(timestamp_at(epoch), 100_000);
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::TimeDelta;
+use mavlink::MavHeader;
+use mavlink::common::MavSeverity;
+use mavlink::common::STATUSTEXT_DATA;
+
+use crate::utils::mavlink::parse_status_text;
+
+/// Chunked texts whose last chunk did not arrive in time are logged as incomplete
+const CHUNK_TIMEOUT: TimeDelta = TimeDelta::seconds(2);
+
+/// Severities from the most to the least important
+pub const SEVERITIES: [MavSeverity; 8] = [
+    MavSeverity::MAV_SEVERITY_EMERGENCY,
+    MavSeverity::MAV_SEVERITY_ALERT,
+    MavSeverity::MAV_SEVERITY_CRITICAL,
+    MavSeverity::MAV_SEVERITY_ERROR,
+    MavSeverity::MAV_SEVERITY_WARNING,
+    MavSeverity::MAV_SEVERITY_NOTICE,
+    MavSeverity::MAV_SEVERITY_INFO,
+    MavSeverity::MAV_SEVERITY_DEBUG,
+];
+
+#[derive(Clone)]
+pub struct StatusTextEntry {
+    pub received_at: DateTime<Local>,
+    pub system_id: u8,
+    pub component_id: u8,
+    pub severity: MavSeverity,
+    pub text: String,
+    pub is_complete: bool,
+}
+
+struct PendingStatusText {
+    started_at: DateTime<Local>,
+    severity: MavSeverity,
+    chunks: BTreeMap<u8, String>,
+}
+
+pub struct StatusTextLog {
+    entries: Vec<StatusTextEntry>,
+    pending: HashMap<(u8, u8, u16), PendingStatusText>,
+    /// least important severity that is still shown
+    pub severity_filter: MavSeverity,
+}
+
+impl Default for StatusTextLog {
+    fn default() -> Self {
+        StatusTextLog {
+            entries: vec![],
+            pending: HashMap::new(),
+            severity_filter: MavSeverity::MAV_SEVERITY_DEBUG,
+        }
+    }
+}
+
+impl StatusTextLog {
+    pub fn push(&mut self, header: &MavHeader, data: &STATUSTEXT_DATA, now: DateTime<Local>) {
+        self.flush_stale(now);
+        let text = parse_status_text(&data.text);
+        // id 0 means the text fits into a single message
+        if data.id == 0 {
+            self.entries.push(StatusTextEntry {
+                received_at: now,
+                system_id: header.system_id,
+                component_id: header.component_id,
+                severity: data.severity,
+                text,
+                is_complete: true,
+            });
+            return;
+        }
+
+        let key = (header.system_id, header.component_id, data.id);
+        let pending = self
+            .pending
+            .entry(key)
+            .or_insert_with(|| PendingStatusText {
+                started_at: now,
+                severity: data.severity,
+                chunks: BTreeMap::new(),
+            });
+        pending.chunks.insert(data.chunk_seq, text);
+
+        // the last chunk is the one that does not fill the whole text field
+        let is_last_chunk = data.text.contains(&0);
+        if is_last_chunk {
+            let pending = self.pending.remove(&key).unwrap();
+            let is_complete = pending.chunks.len() == data.chunk_seq as usize + 1;
+            self.entries
+                .push(Self::assemble(key, pending, now, is_complete));
+        }
+    }
+
+    fn flush_stale(&mut self, now: DateTime<Local>) {
+        let stale_keys = self
+            .pending
+            .iter()
+            .filter(|(_, p)| now - p.started_at > CHUNK_TIMEOUT)
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+        for key in stale_keys {
+            let pending = self.pending.remove(&key).unwrap();
+            self.entries.push(Self::assemble(key, pending, now, false));
+        }
+    }
+
+    fn assemble(
+        (system_id, component_id, _): (u8, u8, u16),
+        pending: PendingStatusText,
+        now: DateTime<Local>,
+        is_complete: bool,
+    ) -> StatusTextEntry {
+        StatusTextEntry {
+            received_at: now,
+            system_id,
+            component_id,
+            severity: pending.severity,
+            text: pending.chunks.into_values().collect::<String>(),
+            is_complete,
+        }
+    }
+
+    pub fn entries(&self) -> &[StatusTextEntry] {
+        &self.entries
+    }
+
+    pub fn filtered(&self) -> Vec<&StatusTextEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.severity as u32 <= self.severity_filter as u32)
+            .collect()
+    }
+
+    pub fn cycle_severity_filter(&mut self) {
+        let position = SEVERITIES
+            .iter()
+            .position(|s| *s == self.severity_filter)
+            .unwrap_or(0);
+        self.severity_filter = SEVERITIES[(position + SEVERITIES.len() - 1) % SEVERITIES.len()];
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        for entry in self.filtered() {
+            writeln!(file, "{}", format_entry(entry))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn severity_name(severity: MavSeverity) -> String {
+    format!("{:?}", severity).replace("MAV_SEVERITY_", "")
+}
+
+pub fn format_entry(entry: &StatusTextEntry) -> String {
+    format!(
+        "{} [{}:{}] {:<9} {}{}",
+        entry.received_at.format("%H:%M:%S%.3f"),
+        entry.system_id,
+        entry.component_id,
+        severity_name(entry.severity),
+        entry.text,
+        if entry.is_complete {
+            ""
+        } else {
+            " (incomplete)"
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use chrono::TimeDelta;
+    use mavlink::MavHeader;
+    use mavlink::common::MavSeverity;
+    use mavlink::common::STATUSTEXT_DATA;
+
+    use crate::status_text::StatusTextLog;
+
+    fn status_text(text: &str, id: u16, chunk_seq: u8) -> STATUSTEXT_DATA {
+        let mut array = [0u8; 50];
+        for (i, ch) in text.chars().enumerate() {
+            array[i] = ch as u8;
+        }
+        STATUSTEXT_DATA {
+            severity: MavSeverity::MAV_SEVERITY_WARNING,
+            text: array,
+            id,
+            chunk_seq,
+        }
+    }
+
+    #[test]
+    fn test_single_message() {
+        let mut log = StatusTextLog::default();
+        log.push(
+            &MavHeader::default(),
+            &status_text("PreArm: Gyros not healthy", 0, 0),
+            Local::now(),
+        );
+        let entries = log.filtered();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].text, "PreArm: Gyros not healthy");
+        assert!(entries[0].is_complete);
+    }
+
+    #[test]
+    fn test_chunked_message() {
+        let mut log = StatusTextLog::default();
+        let first_chunk = "A".repeat(50);
+        let now = Local::now();
+        log.push(&MavHeader::default(), &status_text(&first_chunk, 7, 0), now);
+        assert!(log.filtered().is_empty());
+        log.push(&MavHeader::default(), &status_text("tail", 7, 1), now);
+        let entries = log.filtered();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].text, format!("{}tail", first_chunk));
+        assert!(entries[0].is_complete);
+    }
+
+    #[test]
+    fn test_stale_chunks_are_flushed() {
+        let mut log = StatusTextLog::default();
+        let now = Local::now();
+        log.push(
+            &MavHeader::default(),
+            &status_text(&"B".repeat(50), 3, 0),
+            now,
+        );
+        log.push(
+            &MavHeader::default(),
+            &status_text("next", 0, 0),
+            now + TimeDelta::seconds(5),
+        );
+        let entries = log.filtered();
+        assert_eq!(entries.len(), 2);
+        assert!(!entries[0].is_complete);
+        assert_eq!(entries[1].text, "next");
+    }
+
+    #[test]
+    fn test_severity_filter() {
+        let mut log = StatusTextLog::default();
+        log.push(
+            &MavHeader::default(),
+            &status_text("warning", 0, 0),
+            Local::now(),
+        );
+        log.severity_filter = MavSeverity::MAV_SEVERITY_ERROR;
+        assert!(log.filtered().is_empty());
+        log.cycle_severity_filter();
+        assert_eq!(log.severity_filter, MavSeverity::MAV_SEVERITY_CRITICAL);
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::TimeDelta;
+use mavlink::Message;
+use mavlink::common::MavMessage;
+
+/// Measured rates are averaged over this window
+const RATE_WINDOW: TimeDelta = TimeDelta::seconds(5);
+
+/// Rates that can be requested, 0 stops the message
+pub const RATE_PRESETS_HZ: [f32; 9] = [0.0, 0.5, 1.0, 2.0, 4.0, 5.0, 10.0, 20.0, 50.0];
+
+/// Messages listed even before they are received, so they can be requested on a silent link
+const DEFAULT_MESSAGES: [&str; 11] = [
+    "SYS_STATUS",
+    "GPS_RAW_INT",
+    "ATTITUDE",
+    "GLOBAL_POSITION_INT",
+    "VFR_HUD",
+    "RC_CHANNELS",
+    "SERVO_OUTPUT_RAW",
+    "BATTERY_STATUS",
+    "EXTENDED_SYS_STATE",
+    "HOME_POSITION",
+    "AUTOPILOT_VERSION",
+];
+
+pub struct StreamRate {
+    pub message_id: u32,
+    pub message_name: String,
+    received_at: VecDeque<DateTime<Local>>,
+    pub desired_hz: Option<f32>,
+}
+
+impl StreamRate {
+    fn new(message_id: u32, message_name: String) -> Self {
+        StreamRate {
+            message_id,
+            message_name,
+            received_at: VecDeque::new(),
+            desired_hz: None,
+        }
+    }
+
+    pub fn measured_hz(&self, now: DateTime<Local>) -> f32 {
+        let received_num = self
+            .received_at
+            .iter()
+            .filter(|t| now - **t <= RATE_WINDOW)
+            .count();
+        received_num as f32 / RATE_WINDOW.as_seconds_f32()
+    }
+}
+
+pub struct StreamRates {
+    rates: BTreeMap<u32, StreamRate>,
+}
+
+impl Default for StreamRates {
+    fn default() -> Self {
+        let rates = DEFAULT_MESSAGES
+            .iter()
+            .filter_map(|name| MavMessage::message_id_from_name(name).ok())
+            .map(|id| (id, StreamRate::new(id, message_name(id))))
+            .collect();
+        StreamRates { rates }
+    }
+}
+
+impl StreamRates {
+    pub fn record<M: Message>(&mut self, message: &M, now: DateTime<Local>) {
+        let rate = self.rates.entry(message.message_id()).or_insert_with(|| {
+            StreamRate::new(message.message_id(), message.message_name().to_string())
+        });
+        rate.received_at.push_back(now);
+        while rate
+            .received_at
+            .front()
+            .is_some_and(|t| now - *t > RATE_WINDOW)
+        {
+            rate.received_at.pop_front();
+        }
+    }
+
+    pub fn rows(&self) -> Vec<&StreamRate> {
+        self.rates.values().collect()
+    }
+
+    /// Moves the desired rate of a message to the next preset and returns it
+    pub fn change_desired_rate(&mut self, message_id: u32, is_increase: bool) -> Option<f32> {
+        let rate = self.rates.get_mut(&message_id)?;
+        let position = rate
+            .desired_hz
+            .and_then(|hz| RATE_PRESETS_HZ.iter().position(|p| *p == hz));
+        let next_position = match (position, is_increase) {
+            (None, true) => RATE_PRESETS_HZ.iter().position(|p| *p == 1.0).unwrap(),
+            (None, false) => 0,
+            (Some(p), true) => (p + 1).min(RATE_PRESETS_HZ.len() - 1),
+            (Some(p), false) => p.saturating_sub(1),
+        };
+        rate.desired_hz = Some(RATE_PRESETS_HZ[next_position]);
+        rate.desired_hz
+    }
+}
+
+/// Interval for MAV_CMD_SET_MESSAGE_INTERVAL, -1 disables the message
+pub fn interval_us(rate_hz: f32) -> i32 {
+    if rate_hz <= 0.0 {
+        -1
+    } else {
+        (1_000_000.0 / rate_hz) as i32
+    }
+}
+
+pub fn message_name(message_id: u32) -> String {
+    MavMessage::default_message_from_id(message_id)
+        .map(|m| m.message_name().to_string())
+        .unwrap_or(format!("UNKNOWN_{}", message_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use chrono::TimeDelta;
+    use mavlink::Message;
+    use mavlink::common::ATTITUDE_DATA;
+    use mavlink::common::MavMessage;
+
+    use crate::streams::StreamRates;
+    use crate::streams::interval_us;
+
+    #[test]
+    fn test_measured_rate() {
+        let mut rates = StreamRates::default();
+        let message = MavMessage::ATTITUDE(ATTITUDE_DATA::default());
+        let start = Local::now();
+        for i in 0..50 {
+            rates.record(&message, start + TimeDelta::milliseconds(100 * i));
+        }
+        let now = start + TimeDelta::milliseconds(4_900);
+        let attitude = rates
+            .rows()
+            .into_iter()
+            .find(|r| r.message_id == message.message_id())
+            .unwrap();
+        assert_eq!(attitude.measured_hz(now), 10.0);
+        assert_eq!(attitude.measured_hz(now + TimeDelta::seconds(10)), 0.0);
+    }
+
+    #[test]
+    fn test_change_desired_rate() {
+        let mut rates = StreamRates::default();
+        let attitude_id = MavMessage::message_id_from_name("ATTITUDE").unwrap();
+        assert_eq!(rates.change_desired_rate(attitude_id, true), Some(1.0));
+        assert_eq!(rates.change_desired_rate(attitude_id, true), Some(2.0));
+        assert_eq!(rates.change_desired_rate(attitude_id, false), Some(1.0));
+        assert_eq!(rates.change_desired_rate(9999, true), None);
+    }
+
+    #[test]
+    fn test_interval_us() {
+        assert_eq!(interval_us(10.0), 100_000);
+        assert_eq!(interval_us(0.0), -1);
+    }
+}
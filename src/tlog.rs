@@ -0,0 +1,178 @@
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::TimeDelta;
+use chrono::TimeZone;
+use mavlink::MAV_STX;
+use mavlink::MAV_STX_V2;
+
+use crate::frame::expected_frame_len;
+
+const TIMESTAMP_LEN: usize = 8;
+
+/// Frame of a recording with the time it was received
+pub struct TlogEntry {
+    pub timestamp: DateTime<Local>,
+    pub raw: Vec<u8>,
+}
+
+/// Writes frames as Mission Planner and QGroundControl do: big-endian microseconds
+/// since the Unix epoch followed by the frame bytes
+pub struct TlogWriter {
+    file: File,
+    pub path: PathBuf,
+    pub frames_written: usize,
+}
+
+impl TlogWriter {
+    /// Appends to an existing recording, so a paused recording can be resumed
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(TlogWriter {
+            file,
+            path: path.to_path_buf(),
+            frames_written: 0,
+        })
+    }
+
+    pub fn write(&mut self, timestamp: DateTime<Local>, raw: &[u8]) -> io::Result<()> {
+        let mut entry = Vec::with_capacity(8 + raw.len());
+        entry.extend_from_slice(&(timestamp.timestamp_micros() as u64).to_be_bytes());
+        entry.extend_from_slice(raw);
+        self.file.write_all(&entry)?;
+        self.frames_written += 1;
+        Ok(())
+    }
+}
+
+/// Reads a whole recording, bytes that do not start an entry are skipped
+pub fn read(path: &Path) -> io::Result<Vec<TlogEntry>> {
+    let bytes = std::fs::read(path)?;
+    let mut entries = vec![];
+    let mut offset = 0;
+    let mut previous = None;
+    while offset + TIMESTAMP_LEN < bytes.len() {
+        let Some(timestamp) = entry_timestamp(&bytes, offset, previous) else {
+            offset += 1;
+            continue;
+        };
+        let frame_start = offset + TIMESTAMP_LEN;
+        // a frame cut at the end of the file is kept as a truncated one, a frame cut in the
+        // middle ends where the next entry starts
+        let frame_end = match expected_frame_len(&bytes[frame_start..]) {
+            Some(len) if frame_start + len >= bytes.len() => bytes.len(),
+            Some(len) if entry_timestamp(&bytes, frame_start + len, Some(timestamp)).is_some() => {
+                frame_start + len
+            }
+            _ => (frame_start + 1..bytes.len())
+                .find(|i| entry_timestamp(&bytes, *i, Some(timestamp)).is_some())
+                .unwrap_or(bytes.len()),
+        };
+        entries.push(TlogEntry {
+            timestamp,
+            raw: bytes[frame_start..frame_end].to_vec(),
+        });
+        previous = Some(timestamp);
+        offset = frame_end;
+    }
+    Ok(entries)
+}
+
+/// Timestamp of the entry at the offset when the bytes there look like one: a time close
+/// to the previous entry followed by the start of a frame
+fn entry_timestamp(
+    bytes: &[u8],
+    offset: usize,
+    previous: Option<DateTime<Local>>,
+) -> Option<DateTime<Local>> {
+    let frame_start = offset + TIMESTAMP_LEN;
+    if !matches!(bytes.get(frame_start), Some(&MAV_STX) | Some(&MAV_STX_V2)) {
+        return None;
+    }
+    let micros = u64::from_be_bytes(bytes[offset..frame_start].try_into().unwrap());
+    let timestamp = i64::try_from(micros)
+        .ok()
+        .and_then(|micros| Local.timestamp_micros(micros).single())?;
+    previous
+        .is_none_or(|previous| (timestamp - previous).abs() <= TimeDelta::days(1))
+        .then_some(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use chrono::TimeDelta;
+    use chrono::TimeZone;
+
+    use crate::tlog::TlogWriter;
+    use crate::tlog::read;
+
+    #[test]
+    fn test_write_tlog() {
+        let path = std::env::temp_dir().join("mavlink_debugger_test_write.tlog");
+        let _ = std::fs::remove_file(&path);
+        let timestamp = Local.timestamp_opt(1_700_000_000, 123_000).unwrap();
+        let mut writer = TlogWriter::open(&path).unwrap();
+        writer.write(timestamp, &[0xFD, 0x01]).unwrap();
+        drop(writer);
+        // resumed recordings keep the frames written before
+        let mut writer = TlogWriter::open(&path).unwrap();
+        writer.write(timestamp, &[0xFE]).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let micros = 1_700_000_000_000_123u64.to_be_bytes();
+        assert_eq!(&bytes[..8], &micros);
+        assert_eq!(&bytes[8..10], &[0xFD, 0x01]);
+        assert_eq!(&bytes[10..18], &micros);
+        assert_eq!(&bytes[18..], &[0xFE]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_tlog() {
+        let path = std::env::temp_dir().join("mavlink_debugger_test_read.tlog");
+        let _ = std::fs::remove_file(&path);
+        let timestamp = Local.timestamp_opt(1_700_000_000, 0).unwrap();
+        let heartbeat = [0xFE, 9, 0, 1, 1, 0, 0, 0, 0, 0, 2, 3, 81, 4, 3, 0x1C, 0x7F];
+        let mut writer = TlogWriter::open(&path).unwrap();
+        writer.write(timestamp, &heartbeat).unwrap();
+        writer.write(timestamp, &heartbeat[..5]).unwrap();
+
+        let entries = read(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].timestamp, timestamp);
+        assert_eq!(entries[0].raw, heartbeat);
+        assert_eq!(entries[1].raw, heartbeat[..5]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_tlog_with_truncated_entry() {
+        let path = std::env::temp_dir().join("mavlink_debugger_test_read_truncated.tlog");
+        let _ = std::fs::remove_file(&path);
+        let timestamp = Local.timestamp_opt(1_700_000_000, 0).unwrap();
+        let heartbeat = [0xFE, 9, 0, 1, 1, 0, 0, 0, 0, 0, 2, 3, 81, 4, 3, 0x1C, 0x7F];
+        let mut writer = TlogWriter::open(&path).unwrap();
+        writer.write(timestamp, &heartbeat).unwrap();
+        writer.write(timestamp, &heartbeat[..5]).unwrap();
+        writer.write(timestamp, &heartbeat[..2]).unwrap();
+        for i in 1..=2 {
+            writer
+                .write(timestamp + TimeDelta::seconds(i), &heartbeat)
+                .unwrap();
+        }
+
+        let entries = read(&path).unwrap();
+        let lengths = entries.iter().map(|e| e.raw.len()).collect::<Vec<_>>();
+        assert_eq!(lengths, [17, 5, 2, 17, 17]);
+        assert_eq!(entries[3].timestamp, timestamp + TimeDelta::seconds(1));
+        assert_eq!(entries[4].raw, heartbeat);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
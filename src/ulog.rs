@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use crate::dialect::DialectMessage;
+use crate::dialect::LOG_MESSAGE_ID_BASE;
+use crate::frame::ReceivedFrame;
+use crate::onboard_log::log_frame;
+use crate::runtime_dialect::FieldDefinition;
+use crate::runtime_dialect::FieldType;
+use crate::runtime_dialect::MessageDefinition;
+
+const MAGIC: [u8; 7] = [b'U', b'L', b'o', b'g', 0x01, 0x12, 0x35];
+const HEADER_LEN: usize = 16;
+const MESSAGE_HEADER_LEN: usize = 3;
+/// Logged strings get an id above the 16 bit ids of subscriptions
+const LOGGING_ID: u32 = LOG_MESSAGE_ID_BASE + 0x10000;
+const MAX_LOGGING_TEXT_LEN: usize = 512;
+const TIMESTAMP_LEN: usize = 8;
+/// Nesting of formats deeper than this is taken for a format that refers to itself
+const MAX_FORMAT_DEPTH: usize = 16;
+/// A message body of at most u16::MAX bytes cannot hold more fields
+const MAX_FIELDS: usize = u16::MAX as usize;
+
+/// Reads a PX4 .ulg log, logged topics and strings become messages
+pub fn read<M: DialectMessage>(path: &Path) -> io::Result<Vec<ReceivedFrame<M>>> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() < HEADER_LEN || !bytes.starts_with(&MAGIC) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a ULog file",
+        ));
+    }
+    let logging = logging_definition();
+    let mut formats = HashMap::new();
+    let mut subscriptions: HashMap<u16, &'static MessageDefinition> = HashMap::new();
+    let mut frames = vec![];
+    let mut offset = HEADER_LEN;
+    while offset + MESSAGE_HEADER_LEN <= bytes.len() {
+        let size = u16::from_le_bytes([bytes[offset], bytes[offset + 1]]) as usize;
+        let message_type = bytes[offset + 2];
+        let end = offset + MESSAGE_HEADER_LEN + size;
+        if end > bytes.len() {
+            break;
+        }
+        let raw = &bytes[offset..end];
+        let body = &raw[MESSAGE_HEADER_LEN..];
+        offset = end;
+        let record = match message_type {
+            b'F' => {
+                if let Some((name, fields)) = parse_format(&text(body)) {
+                    formats.insert(name, fields);
+                }
+                None
+            }
+            b'A' if body.len() > 3 => {
+                let multi_id = body[0];
+                let msg_id = u16::from_le_bytes([body[1], body[2]]);
+                let name = text(&body[3..]);
+                if let Some(definition) = subscription_definition(&formats, &name, multi_id, msg_id)
+                {
+                    subscriptions.insert(msg_id, definition);
+                }
+                None
+            }
+            b'D' if body.len() >= 2 + TIMESTAMP_LEN => {
+                let msg_id = u16::from_le_bytes([body[0], body[1]]);
+                subscriptions
+                    .get(&msg_id)
+                    .map(|definition| (*definition, body[2..].to_vec(), timestamp(&body[2..])))
+            }
+            b'L' if body.len() > TIMESTAMP_LEN => {
+                Some((logging, body.to_vec(), timestamp(&body[1..])))
+            }
+            // tagged strings are shown without their tag
+            b'C' if body.len() >= 3 + TIMESTAMP_LEN => {
+                let payload = [&body[..1], &body[3..]].concat();
+                Some((logging, payload, timestamp(&body[3..])))
+            }
+            _ => None,
+        };
+        if let Some((definition, payload, micros)) = record {
+            frames.push(log_frame(
+                definition,
+                payload,
+                raw.to_vec(),
+                micros,
+                frames.len() as u8,
+            ));
+        }
+    }
+    Ok(frames)
+}
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn timestamp(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..TIMESTAMP_LEN].try_into().unwrap())
+}
+
+/// Field of a format, the type is either a basic type or another format
+struct FormatField {
+    type_name: String,
+    array_len: Option<usize>,
+    name: String,
+}
+
+/// Parses "name:type field;type field[n];..."
+fn parse_format(format: &str) -> Option<(String, Vec<FormatField>)> {
+    let (name, fields) = format.split_once(':')?;
+    let fields = fields
+        .split(';')
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            let (type_name, name) = field.split_once(' ')?;
+            let (type_name, array_len) = match type_name.split_once('[') {
+                Some((type_name, len)) => {
+                    (type_name, Some(len.trim_end_matches(']').parse().ok()?))
+                }
+                None => (type_name, None),
+            };
+            Some(FormatField {
+                type_name: type_name.to_string(),
+                array_len,
+                name: name.to_string(),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some((name.to_string(), fields))
+}
+
+fn subscription_definition(
+    formats: &HashMap<String, Vec<FormatField>>,
+    name: &str,
+    multi_id: u8,
+    msg_id: u16,
+) -> Option<&'static MessageDefinition> {
+    let mut fields = vec![];
+    flatten(formats, name, "", 0, &mut fields)?;
+    // instances of the same topic are told apart by their multi id
+    let name = match multi_id {
+        0 => name.to_string(),
+        _ => format!("{name}_{multi_id}"),
+    };
+    let definition = MessageDefinition {
+        id: LOG_MESSAGE_ID_BASE + msg_id as u32,
+        name,
+        fields,
+        extra_crc: 0,
+    };
+    Some(Box::leak(Box::new(definition)))
+}
+
+/// Nested formats become fields named "outer.inner", "outer[0].inner" for arrays,
+/// None for unknown, self-referencing or oversized formats
+fn flatten(
+    formats: &HashMap<String, Vec<FormatField>>,
+    format_name: &str,
+    prefix: &str,
+    depth: usize,
+    fields: &mut Vec<FieldDefinition>,
+) -> Option<()> {
+    if depth > MAX_FORMAT_DEPTH || fields.len() > MAX_FIELDS {
+        return None;
+    }
+    for field in formats.get(format_name)? {
+        let name = format!("{prefix}{}", field.name);
+        let field_type = match field.type_name.as_str() {
+            "bool" => Some(FieldType::UInt8),
+            type_name => FieldType::parse(type_name),
+        };
+        match field_type {
+            Some(field_type) => fields.push(FieldDefinition {
+                name,
+                field_type,
+                array_len: field.array_len,
+                enum_name: None,
+                units: None,
+                is_bitmask: false,
+                is_extension: false,
+                declaration_index: fields.len(),
+            }),
+            None => match field.array_len {
+                Some(len) if len > MAX_FIELDS => return None,
+                Some(len) => {
+                    for i in 0..len {
+                        let prefix = format!("{name}[{i}].");
+                        flatten(formats, &field.type_name, &prefix, depth + 1, fields)?;
+                    }
+                }
+                None => flatten(
+                    formats,
+                    &field.type_name,
+                    &format!("{name}."),
+                    depth + 1,
+                    fields,
+                )?,
+            },
+        }
+    }
+    Some(())
+}
+
+fn logging_definition() -> &'static MessageDefinition {
+    let field = |declaration_index, name: &str, field_type, array_len| FieldDefinition {
+        name: name.to_string(),
+        field_type,
+        array_len,
+        enum_name: None,
+        units: None,
+        is_bitmask: false,
+        is_extension: false,
+        declaration_index,
+    };
+    let definition = MessageDefinition {
+        id: LOGGING_ID,
+        name: "LOGGING".to_string(),
+        fields: vec![
+            field(0, "log_level", FieldType::UInt8, None),
+            field(1, "timestamp", FieldType::UInt64, None),
+            field(2, "message", FieldType::Char, Some(MAX_LOGGING_TEXT_LEN)),
+        ],
+        extra_crc: 0,
+    };
+    Box::leak(Box::new(definition))
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::common::MavMessage;
+
+    use crate::dialect::DialectMessage;
+    use crate::dialect::ExtendedMessage;
+    use crate::frame::FrameContent;
+    use crate::onboard_log::boot_time;
+    use crate::ulog::MAGIC;
+    use crate::ulog::read;
+
+    fn message(message_type: u8, body: &[u8]) -> Vec<u8> {
+        let mut message = (body.len() as u16).to_le_bytes().to_vec();
+        message.push(message_type);
+        message.extend(body);
+        message
+    }
+
+    #[test]
+    fn test_read_ulog() {
+        let path = std::env::temp_dir().join("mavlink_debugger_test.ulg");
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(1);
+        bytes.extend(0u64.to_le_bytes());
+        bytes.extend(message(b'F', b"vec:float x;float y;"));
+        bytes.extend(message(
+            b'F',
+            b"pose:uint64_t timestamp;vec[2] points;bool valid;",
+        ));
+        bytes.extend(message(b'A', &[1, 7, 0, b'p', b'o', b's', b'e']));
+        let mut data = vec![7, 0];
+        data.extend(3_000_000u64.to_le_bytes());
+        for value in [1.0f32, 2.0, 3.0, 4.5] {
+            data.extend(value.to_le_bytes());
+        }
+        data.push(1);
+        bytes.extend(message(b'D', &data));
+        let mut logging = vec![6];
+        logging.extend(4_000_000u64.to_le_bytes());
+        logging.extend(b"Takeoff detected");
+        bytes.extend(message(b'L', &logging));
+        std::fs::write(&path, &bytes).unwrap();
+
+        let frames = read::<ExtendedMessage<MavMessage>>(&path).unwrap();
+        assert_eq!(frames.len(), 2);
+        let FrameContent::Message { message, .. } = &frames[0].content else {
+            panic!("pose is subscribed");
+        };
+        let fields = message
+            .fields()
+            .iter()
+            .map(|field| (field.name.clone(), field.formatted_value()))
+            .collect::<Vec<_>>();
+        assert_eq!(fields[0], ("timestamp".to_string(), "3000000".to_string()));
+        assert_eq!(fields[4], ("points[1].y".to_string(), "4.5".to_string()));
+        assert_eq!(fields[5], ("valid".to_string(), "1".to_string()));
+        assert_eq!(frames[0].received, boot_time(3_000_000));
+
+        let FrameContent::Message { message, .. } = &frames[1].content else {
+            panic!("strings are logged");
+        };
+        assert_eq!(
+            message.fields()[2].formatted_value(),
+            "\"Takeoff detected\""
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_ulog_with_recursive_formats() {
+        let path = std::env::temp_dir().join("mavlink_debugger_test_recursive.ulg");
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(1);
+        bytes.extend(0u64.to_le_bytes());
+        bytes.extend(message(b'F', b"self:uint64_t timestamp;self inner;"));
+        bytes.extend(message(b'F', b"a:uint64_t timestamp;b[2] inner;"));
+        bytes.extend(message(b'F', b"b:a inner;"));
+        bytes.extend(message(b'F', b"huge:uint64_t timestamp;b[100000] inner;"));
+        for (msg_id, name) in [(1, &b"self"[..]), (2, b"a"), (3, b"huge")] {
+            let mut subscription = vec![0, msg_id, 0];
+            subscription.extend(name);
+            bytes.extend(message(b'A', &subscription));
+            let mut data = vec![msg_id, 0];
+            data.extend(1_000_000u64.to_le_bytes());
+            bytes.extend(message(b'D', &data));
+        }
+        std::fs::write(&path, &bytes).unwrap();
+
+        let frames = read::<ExtendedMessage<MavMessage>>(&path).unwrap();
+        assert!(frames.is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -18,15 +18,8 @@ pub mod tui {
 
         pub fn check_allowed(&mut self, now: SystemTime) -> bool {
             match self.allowed_at {
-                Some(allowed_at) => {
-                    if allowed_at >= now {
-                        false
-                    } else {
-                        self.allowed_at = Some(now + self.refresh_rate);
-                        true
-                    }
-                }
-                None => {
+                Some(allowed_at) if allowed_at >= now => false,
+                _ => {
                     self.allowed_at = Some(now + self.refresh_rate);
                     true
                 }
@@ -56,6 +49,10 @@ pub mod tui {
 }
 
 pub mod mavlink {
+    use mavlink::common::HEARTBEAT_DATA;
+    use mavlink::common::MavAutopilot;
+    use mavlink::common::MavType;
+
     pub fn decode_param_id(param_id: &[u8; 16]) -> String {
         param_id
             .iter()
@@ -63,6 +60,14 @@ pub mod mavlink {
             .map(|&b| char::from(b))
             .collect()
     }
+    /// Names shorter than 16 characters are null terminated, longer ones are cut
+    pub fn encode_param_id(name: &str) -> [u8; 16] {
+        let mut param_id = [0u8; 16];
+        for (byte, ch) in param_id.iter_mut().zip(name.bytes()) {
+            *byte = ch;
+        }
+        param_id
+    }
     pub fn parse_status_text(text: &[u8; 50]) -> String {
         text.iter()
             .filter(|&b| *b != 0)
@@ -70,9 +75,21 @@ pub mod mavlink {
             .collect()
     }
 
+    /// Ground stations and peripherals also send heartbeats, only flight controllers are valid targets
+    pub fn is_autopilot_heartbeat(heartbeat: &HEARTBEAT_DATA) -> bool {
+        heartbeat.mavtype != MavType::MAV_TYPE_GCS
+            && heartbeat.autopilot != MavAutopilot::MAV_AUTOPILOT_INVALID
+    }
+
     #[cfg(test)]
     mod tests {
+        use mavlink::common::HEARTBEAT_DATA;
+        use mavlink::common::MavAutopilot;
+        use mavlink::common::MavType;
+
         use crate::utils::mavlink::decode_param_id;
+        use crate::utils::mavlink::encode_param_id;
+        use crate::utils::mavlink::is_autopilot_heartbeat;
         use crate::utils::mavlink::parse_status_text;
 
         #[test]
@@ -82,6 +99,7 @@ pub mod mavlink {
                 array[i] = ch as u8;
             }
             assert_eq!(decode_param_id(&array), "TEST_PARAM".to_string());
+            assert_eq!(encode_param_id("TEST_PARAM"), array);
         }
         #[test]
         fn test_parse_status_text() {
@@ -91,5 +109,20 @@ pub mod mavlink {
             }
             assert_eq!(parse_status_text(&array), "TEST_STATUS_TEXT".to_string());
         }
+        #[test]
+        fn test_is_autopilot_heartbeat() {
+            let autopilot = HEARTBEAT_DATA {
+                mavtype: MavType::MAV_TYPE_QUADROTOR,
+                autopilot: MavAutopilot::MAV_AUTOPILOT_ARDUPILOTMEGA,
+                ..Default::default()
+            };
+            let gcs = HEARTBEAT_DATA {
+                mavtype: MavType::MAV_TYPE_GCS,
+                autopilot: MavAutopilot::MAV_AUTOPILOT_INVALID,
+                ..Default::default()
+            };
+            assert!(is_autopilot_heartbeat(&autopilot));
+            assert!(!is_autopilot_heartbeat(&gcs));
+        }
     }
 }
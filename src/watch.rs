@@ -0,0 +1,302 @@
+use std::io;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::TimeDelta;
+use mavlink::MavHeader;
+
+use crate::dialect::DialectMessage;
+use crate::message_fields::MessageField;
+use crate::plot::PlotSeries;
+
+/// Sliding windows in seconds, changed with (+/-) on the watch screen
+const WINDOWS: [i64; 6] = [1, 5, 10, 30, 60, 300];
+const DEFAULT_WINDOW_INDEX: usize = 2;
+
+/// Count, extremes, mean and standard deviation of values added one at a time
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Statistics {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Sum of squared differences from the mean, Welford's method
+    m2: f64,
+}
+
+impl Statistics {
+    pub fn add(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Population standard deviation
+    pub fn std_dev(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        (self.m2 / self.count as f64).sqrt()
+    }
+}
+
+/// Watched field with its recent values and statistics of the whole session
+pub struct WatchedField {
+    pub series: PlotSeries,
+    pub session: Statistics,
+}
+
+impl WatchedField {
+    pub fn current(&self) -> Option<f64> {
+        self.series.points.back().map(|(_, value)| *value)
+    }
+
+    fn window_values(
+        &self,
+        window: TimeDelta,
+        now: DateTime<Local>,
+    ) -> impl Iterator<Item = &(DateTime<Local>, f64)> {
+        let start = now - window;
+        self.series
+            .points
+            .iter()
+            .filter(move |(t, _)| *t >= start && *t <= now)
+    }
+
+    pub fn window_statistics(&self, window: TimeDelta, now: DateTime<Local>) -> Statistics {
+        let mut statistics = Statistics::default();
+        for (_, value) in self.window_values(window, now) {
+            statistics.add(*value);
+        }
+        statistics
+    }
+
+    /// Change per second between the first and the last value of the window
+    pub fn rate_of_change(&self, window: TimeDelta, now: DateTime<Local>) -> Option<f64> {
+        let mut values = self.window_values(window, now);
+        let (first_time, first) = values.next()?;
+        let (last_time, last) = values.last()?;
+        let seconds = (*last_time - *first_time).num_milliseconds() as f64 / 1000.0;
+        (seconds > 0.0).then(|| (last - first) / seconds)
+    }
+}
+
+/// Fields pinned to the watch screen
+pub struct WatchList {
+    pub fields: Vec<WatchedField>,
+    window_index: usize,
+}
+
+impl Default for WatchList {
+    fn default() -> Self {
+        WatchList {
+            fields: Vec::new(),
+            window_index: DEFAULT_WINDOW_INDEX,
+        }
+    }
+}
+
+impl WatchList {
+    /// Watches the field, or stops watching it when already watched; true when added
+    pub fn toggle(&mut self, header: &MavHeader, message_name: &str, field: &MessageField) -> bool {
+        if let Some(index) = self.position(header, message_name, &field.name) {
+            self.fields.remove(index);
+            return false;
+        }
+        self.fields.push(WatchedField {
+            series: PlotSeries::new(header, message_name, field),
+            session: Statistics::default(),
+        });
+        true
+    }
+
+    pub fn is_watched(&self, header: &MavHeader, message_name: &str, field_name: &str) -> bool {
+        self.position(header, message_name, field_name).is_some()
+    }
+
+    fn position(&self, header: &MavHeader, message_name: &str, field_name: &str) -> Option<usize> {
+        self.fields.iter().position(|f| {
+            f.series.matches(header, message_name) && f.series.field_name == field_name
+        })
+    }
+
+    /// Adds the values of watched fields, messages without watched fields are not decoded
+    pub fn record<M: DialectMessage>(
+        &mut self,
+        header: &MavHeader,
+        message: &M,
+        now: DateTime<Local>,
+    ) {
+        let message_name = message.message_name();
+        if !self
+            .fields
+            .iter()
+            .any(|f| f.series.matches(header, message_name))
+        {
+            return;
+        }
+        let fields = message.fields();
+        for field in self
+            .fields
+            .iter_mut()
+            .filter(|f| f.series.matches(header, message_name))
+        {
+            if let Some(value) = field.series.push_field(&fields, now) {
+                field.session.add(value);
+            }
+        }
+    }
+
+    /// Forgets the values, keeping the watched fields
+    pub fn clear_values(&mut self) {
+        for field in &mut self.fields {
+            field.series.points.clear();
+            field.session = Statistics::default();
+        }
+    }
+
+    pub fn window(&self) -> TimeDelta {
+        TimeDelta::seconds(WINDOWS[self.window_index])
+    }
+
+    pub fn change_window(&mut self, is_longer: bool) {
+        self.window_index = if is_longer {
+            (self.window_index + 1).min(WINDOWS.len() - 1)
+        } else {
+            self.window_index.saturating_sub(1)
+        };
+    }
+
+    /// Statistics of every watched field over the whole session as a text table
+    pub fn report(&self, now: DateTime<Local>) -> String {
+        let mut text = format!("Watch summary at {}\n\n", now.format("%Y-%m-%d %H:%M:%S"));
+        text.push_str(&format!(
+            "{:<40} {:<8} {:>8} {:>12} {:>12} {:>12} {:>12} {:>12} {}\n",
+            "Field", "Id", "Samples", "Last", "Min", "Max", "Mean", "Std dev", "Units"
+        ));
+        for field in &self.fields {
+            let statistics = &field.session;
+            let [last, min, max, mean, std_dev] = if statistics.count == 0 {
+                Default::default()
+            } else {
+                [
+                    field.current().map(format_value).unwrap_or_default(),
+                    format_value(statistics.min),
+                    format_value(statistics.max),
+                    format_value(statistics.mean),
+                    format_value(statistics.std_dev()),
+                ]
+            };
+            text.push_str(&format!(
+                "{:<40} {:<8} {:>8} {:>12} {:>12} {:>12} {:>12} {:>12} {}\n",
+                field.series.name(),
+                format!("{}:{}", field.series.system_id, field.series.component_id),
+                statistics.count,
+                last,
+                min,
+                max,
+                mean,
+                std_dev,
+                field.series.units.as_deref().unwrap_or_default(),
+            ));
+        }
+        text
+    }
+
+    pub fn save_report(&self, path: &Path, now: DateTime<Local>) -> io::Result<()> {
+        std::fs::write(path, self.report(now))
+    }
+}
+
+/// Whole numbers as they are, fractions with three decimals
+pub fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        value.to_string()
+    } else {
+        format!("{:.3}", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use chrono::TimeDelta;
+    use mavlink::MavHeader;
+    use mavlink::common::MavMessage;
+    use mavlink::common::VFR_HUD_DATA;
+
+    use crate::dialect::DialectMessage;
+    use crate::dialect::ExtendedMessage;
+    use crate::watch::Statistics;
+    use crate::watch::WatchList;
+
+    #[test]
+    fn test_statistics() {
+        let mut statistics = Statistics::default();
+        for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            statistics.add(value);
+        }
+        assert_eq!(statistics.count, 8);
+        assert_eq!(statistics.min, 2.0);
+        assert_eq!(statistics.max, 9.0);
+        assert_eq!(statistics.mean, 5.0);
+        assert_eq!(statistics.std_dev(), 2.0);
+    }
+
+    #[test]
+    fn test_watch_window() {
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 0,
+        };
+        let hud = |alt: f32| {
+            ExtendedMessage::Compiled(MavMessage::VFR_HUD(VFR_HUD_DATA {
+                alt,
+                ..Default::default()
+            }))
+        };
+        let alt = hud(0.0)
+            .fields()
+            .into_iter()
+            .find(|f| f.name == "alt")
+            .unwrap();
+        let mut watch = WatchList::default();
+        assert!(watch.toggle(&header, "VFR_HUD", &alt));
+
+        // Climbing 2 m/s for 20 seconds, one message a second
+        let start = Local::now();
+        for i in 0..=20 {
+            watch.record(&header, &hud(2.0 * i as f32), start + TimeDelta::seconds(i));
+        }
+        let now = start + TimeDelta::seconds(20);
+        let field = &watch.fields[0];
+        assert_eq!(field.current(), Some(40.0));
+        assert_eq!(field.session.count, 21);
+        assert_eq!(field.session.mean, 20.0);
+
+        let window = field.window_statistics(watch.window(), now);
+        assert_eq!(window.count, 11);
+        assert_eq!((window.min, window.max, window.mean), (20.0, 40.0, 30.0));
+        assert_eq!(field.rate_of_change(watch.window(), now), Some(2.0));
+
+        let report = watch.report(now);
+        let line = report.lines().nth(3).unwrap();
+        assert!(line.starts_with("VFR_HUD.alt"));
+        let columns = line.split_whitespace().collect::<Vec<_>>();
+        assert_eq!(columns[1..7], ["1:1", "21", "40", "0", "40", "20"]);
+
+        watch.clear_values();
+        assert_eq!(watch.fields[0].current(), None);
+        assert!(watch.is_watched(&header, "VFR_HUD", "alt"));
+    }
+}
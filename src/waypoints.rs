@@ -0,0 +1,137 @@
+use mavlink::common::MISSION_ITEM_INT_DATA;
+use mavlink::common::MavCmd;
+use mavlink::common::MavFrame;
+use num_traits::FromPrimitive;
+
+/// First line of the QGroundControl and Mission Planner waypoint file format
+const WPL_HEADER: &str = "QGC WPL 110";
+
+/// MISSION_ITEM_INT holds x and y scaled, waypoint files hold them in degrees or meters
+fn position_scale(frame: MavFrame) -> f64 {
+    match frame {
+        MavFrame::MAV_FRAME_GLOBAL
+        | MavFrame::MAV_FRAME_GLOBAL_INT
+        | MavFrame::MAV_FRAME_GLOBAL_RELATIVE_ALT
+        | MavFrame::MAV_FRAME_GLOBAL_RELATIVE_ALT_INT
+        | MavFrame::MAV_FRAME_GLOBAL_TERRAIN_ALT
+        | MavFrame::MAV_FRAME_GLOBAL_TERRAIN_ALT_INT => 1e7,
+        // x and y are plain parameters 5 and 6
+        MavFrame::MAV_FRAME_MISSION => 1.0,
+        _ => 1e4,
+    }
+}
+
+pub fn write(items: &[MISSION_ITEM_INT_DATA]) -> String {
+    let mut text = format!("{}\n", WPL_HEADER);
+    for item in items {
+        let scale = position_scale(item.frame);
+        text.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            item.seq,
+            item.current,
+            item.frame as u32,
+            item.command as u32,
+            item.param1,
+            item.param2,
+            item.param3,
+            item.param4,
+            item.x as f64 / scale,
+            item.y as f64 / scale,
+            item.z,
+            item.autocontinue,
+        ));
+    }
+    text
+}
+
+pub fn parse(text: &str) -> Result<Vec<MISSION_ITEM_INT_DATA>, String> {
+    let mut lines = text.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == WPL_HEADER => {}
+        _ => return Err(format!("Not a waypoint file, expected {}", WPL_HEADER)),
+    }
+    lines
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| parse_item(line).ok_or(format!("Invalid item on line {}", index + 1)))
+        .collect()
+}
+
+fn parse_item(line: &str) -> Option<MISSION_ITEM_INT_DATA> {
+    let columns = line.split_whitespace().collect::<Vec<_>>();
+    let [
+        seq,
+        current,
+        frame,
+        command,
+        param1,
+        param2,
+        param3,
+        param4,
+        x,
+        y,
+        z,
+        autocontinue,
+    ] = columns.as_slice()
+    else {
+        return None;
+    };
+    let frame = MavFrame::from_u32(frame.parse().ok()?)?;
+    let scale = position_scale(frame);
+    Some(MISSION_ITEM_INT_DATA {
+        seq: seq.parse().ok()?,
+        current: current.parse().ok()?,
+        frame,
+        command: MavCmd::from_u32(command.parse().ok()?)?,
+        param1: param1.parse().ok()?,
+        param2: param2.parse().ok()?,
+        param3: param3.parse().ok()?,
+        param4: param4.parse().ok()?,
+        x: (x.parse::<f64>().ok()? * scale).round() as i32,
+        y: (y.parse::<f64>().ok()? * scale).round() as i32,
+        z: z.parse().ok()?,
+        autocontinue: autocontinue.parse().ok()?,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::common::MISSION_ITEM_INT_DATA;
+    use mavlink::common::MavCmd;
+    use mavlink::common::MavFrame;
+
+    use crate::waypoints::parse;
+    use crate::waypoints::write;
+
+    #[test]
+    fn test_waypoints_round_trip() {
+        let items = vec![
+            MISSION_ITEM_INT_DATA {
+                seq: 0,
+                frame: MavFrame::MAV_FRAME_GLOBAL,
+                command: MavCmd::MAV_CMD_NAV_WAYPOINT,
+                x: 473977419,
+                y: 85455938,
+                z: 488.0,
+                autocontinue: 1,
+                ..Default::default()
+            },
+            MISSION_ITEM_INT_DATA {
+                seq: 1,
+                frame: MavFrame::MAV_FRAME_GLOBAL_RELATIVE_ALT,
+                command: MavCmd::MAV_CMD_NAV_TAKEOFF,
+                param1: 15.0,
+                z: 20.0,
+                autocontinue: 1,
+                ..Default::default()
+            },
+        ];
+        let text = write(&items);
+        assert_eq!(
+            text.lines().nth(1),
+            Some("0\t0\t0\t16\t0\t0\t0\t0\t47.3977419\t8.5455938\t488\t1")
+        );
+        assert_eq!(parse(&text).unwrap(), items);
+        assert!(parse("0\t0\t0\t16").is_err());
+    }
+}